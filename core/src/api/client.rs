@@ -2,18 +2,19 @@ use crate::api::schemas;
 use crate::api::schemas::{
     AuthError, AuthResponse, LoginRequest, RegisterError, RegisterRequest, RequestParams,
 };
+use crate::api::socket::encode_auth;
 use crate::auth::schemas::Auth;
 use crate::f;
-use crate::helpers::types::{ChatId, UserId};
+use crate::helpers::types::{ChatId, MessageId, UserId};
 use crate::storage::AuthManager;
-use reqwest::{Response, StatusCode};
+use crate::utils::encode_path_segment;
+use reqwest::multipart::{Form, Part};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use url::Url;
-// type WriteMessageWs = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>;
-// type ReadMessageWs = SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>;
 
 #[derive(Clone)]
 pub struct SharedApiClient(Arc<RwLock<ApiClient>>);
@@ -28,27 +29,287 @@ impl SharedApiClient {
         let mut client = self.0.write().await;
         client.register(req).await
     }
-    
+
     pub async fn logout(&self) -> Result<(), AuthError> {
         let mut client = self.0.write().await;
         client.logout().await
     }
 
+    pub async fn user_id(&self) -> Option<UserId> {
+        self.0.read().await.user_id.clone()
+    }
+
+    pub async fn get_users_by_ids(
+        &self,
+        user_ids: Vec<UserId>,
+    ) -> ApiResult<schemas::UserSearchResults> {
+        let mut client = self.0.write().await;
+        client.get_users_by_ids(user_ids).await
+    }
+
+    pub async fn get_chats(&self) -> ApiResult<schemas::ChatSearchResults> {
+        let mut client = self.0.write().await;
+        client.get_chats().await
+    }
+
+    pub async fn get_chat(&self, chat_id: ChatId) -> ApiResult<schemas::ChatModel> {
+        let mut client = self.0.write().await;
+        client.get_chat(chat_id).await
+    }
+
+    pub async fn get_messages(
+        &self,
+        chat_id: ChatId,
+        before_seq: Option<u64>,
+        limit: u32,
+    ) -> ApiResult<schemas::MessagePage> {
+        let mut client = self.0.write().await;
+        client.get_messages(chat_id, before_seq, limit).await
+    }
+
+    pub async fn search_messages(
+        &self,
+        query: String,
+        limit: u32,
+    ) -> ApiResult<schemas::MessageSearchResults> {
+        let mut client = self.0.write().await;
+        client.search_messages(query, limit).await
+    }
+
+    pub async fn send_message(
+        &self,
+        message: schemas::NewMessage,
+    ) -> ApiResult<schemas::MessageModel> {
+        let mut client = self.0.write().await;
+        client.send_message(message).await
+    }
+
+    pub async fn get_capabilities(&self) -> ApiResult<schemas::ServerCapabilities> {
+        let mut client = self.0.write().await;
+        client.get_capabilities().await
+    }
+
+    pub async fn mark_chat_as_read(&self, chat_id: ChatId) -> ApiResult<()> {
+        let mut client = self.0.write().await;
+        client.mark_chat_as_read(chat_id).await
+    }
+
+    pub async fn edit_message(
+        &self,
+        chat_id: ChatId,
+        message_id: &MessageId,
+        text: String,
+    ) -> ApiResult<schemas::MessageModel> {
+        let mut client = self.0.write().await;
+        client.edit_message(chat_id, message_id, text).await
+    }
+
+    pub async fn delete_message(
+        &self,
+        chat_id: ChatId,
+        message_id: &MessageId,
+        scope: schemas::DeleteScope,
+    ) -> ApiResult<()> {
+        let mut client = self.0.write().await;
+        client.delete_message(chat_id, message_id, scope).await
+    }
+
+    pub async fn add_reaction(
+        &self,
+        chat_id: ChatId,
+        message_id: &MessageId,
+        emoji: String,
+    ) -> ApiResult<()> {
+        let mut client = self.0.write().await;
+        client.add_reaction(chat_id, message_id, emoji).await
+    }
+
+    pub async fn remove_reaction(
+        &self,
+        chat_id: ChatId,
+        message_id: &MessageId,
+        emoji: String,
+    ) -> ApiResult<()> {
+        let mut client = self.0.write().await;
+        client.remove_reaction(chat_id, message_id, emoji).await
+    }
+
+    /// Uploads the file in chunks, one request each, reporting the progress after every chunk.
+    ///
+    /// Other requests can go through between the chunks.
+    pub async fn upload_attachment(
+        &self,
+        chat_id: ChatId,
+        file: schemas::FileUpload,
+        mut on_progress: impl FnMut(schemas::UploadProgress),
+    ) -> ApiResult<schemas::Attachment> {
+        let upload_id = uuid::Uuid::new_v4().to_string();
+        let total = file.data.len();
+        let mut offset = 0;
+        loop {
+            let end = (offset + UPLOAD_CHUNK_SIZE).min(total);
+            let attachment = {
+                let mut client = self.0.write().await;
+                client
+                    .upload_chunk(chat_id, &upload_id, &file, offset, end)
+                    .await?
+            };
+            on_progress(schemas::UploadProgress {
+                sent: end as u64,
+                total: total as u64,
+            });
+            if let Some(attachment) = attachment {
+                return Ok(attachment);
+            }
+            if end == total {
+                return Err(ApiError::DataError(
+                    "Upload finished without an attachment".to_string(),
+                ));
+            }
+            offset = end;
+        }
+    }
+
+    pub async fn search_users(&self, username: String) -> ApiResult<schemas::UserSearchResults> {
+        let mut client = self.0.write().await;
+        client.search_users(username).await
+    }
+
+    pub async fn create_chat(&self, chat: schemas::NewChatModel) -> ApiResult<schemas::ChatModel> {
+        let mut client = self.0.write().await;
+        client.create_chat(chat).await
+    }
+
+    pub async fn rename_chat(
+        &self,
+        chat_id: ChatId,
+        name: Option<String>,
+    ) -> ApiResult<schemas::ChatModel> {
+        let mut client = self.0.write().await;
+        client.rename_chat(chat_id, name).await
+    }
+
+    pub async fn add_members(
+        &self,
+        chat_id: ChatId,
+        user_ids: Vec<UserId>,
+    ) -> ApiResult<schemas::ChatModel> {
+        let mut client = self.0.write().await;
+        client.add_members(chat_id, user_ids).await
+    }
+
+    pub async fn remove_member(
+        &self,
+        chat_id: ChatId,
+        user_id: &UserId,
+    ) -> ApiResult<schemas::ChatModel> {
+        let mut client = self.0.write().await;
+        client.remove_member(chat_id, user_id).await
+    }
+
+    pub async fn set_member_role(
+        &self,
+        chat_id: ChatId,
+        user_id: &UserId,
+        role: schemas::ChatRole,
+    ) -> ApiResult<schemas::ChatModel> {
+        let mut client = self.0.write().await;
+        client.set_member_role(chat_id, user_id, role).await
+    }
+
+    pub async fn leave_chat(&self, chat_id: ChatId) -> ApiResult<()> {
+        let mut client = self.0.write().await;
+        client.leave_chat(chat_id).await
+    }
+
+    pub async fn update_chat_settings(
+        &self,
+        chat_id: ChatId,
+        settings: &schemas::ChatSettings,
+    ) -> ApiResult<schemas::ChatSettings> {
+        let mut client = self.0.write().await;
+        client.update_chat_settings(chat_id, settings).await
+    }
+
+    pub async fn get_folders(&self) -> ApiResult<schemas::ChatFolders> {
+        let mut client = self.0.write().await;
+        client.get_folders().await
+    }
+
+    pub async fn save_folder(
+        &self,
+        folder: &schemas::ChatFolder,
+    ) -> ApiResult<schemas::ChatFolder> {
+        let mut client = self.0.write().await;
+        client.save_folder(folder).await
+    }
+
+    pub async fn delete_folder(&self, folder_id: &str) -> ApiResult<()> {
+        let mut client = self.0.write().await;
+        client.delete_folder(folder_id).await
+    }
+
+    pub async fn get_invites(&self, chat_id: ChatId) -> ApiResult<schemas::ChatInvites> {
+        let mut client = self.0.write().await;
+        client.get_invites(chat_id).await
+    }
+
+    pub async fn create_invite(
+        &self,
+        chat_id: ChatId,
+        invite: schemas::NewChatInvite,
+    ) -> ApiResult<schemas::ChatInvite> {
+        let mut client = self.0.write().await;
+        client.create_invite(chat_id, invite).await
+    }
+
+    pub async fn revoke_invite(&self, chat_id: ChatId, code: &str) -> ApiResult<()> {
+        let mut client = self.0.write().await;
+        client.revoke_invite(chat_id, code).await
+    }
+
+    pub async fn get_invite(&self, code: &str) -> ApiResult<schemas::InvitePreview> {
+        let mut client = self.0.write().await;
+        client.get_invite(code).await
+    }
+
+    pub async fn join_chat(&self, code: &str) -> ApiResult<schemas::ChatModel> {
+        let mut client = self.0.write().await;
+        client.join_chat(code).await
+    }
+
+    pub async fn message_socket_url(&self) -> String {
+        let client = self.0.read().await;
+        client.message_socket_url().to_string()
+    }
+
+    pub async fn message_socket_auth(&self) -> Option<String> {
+        let client = self.0.read().await;
+        client.message_socket_auth()
+    }
+
+    pub async fn refresh_auth(&self) -> ApiResult<()> {
+        let mut client = self.0.write().await;
+        client.refresh_auth().await
+    }
+
     pub fn new(client: ApiClient) -> Self {
         Self(Arc::new(RwLock::new(client)))
     }
 }
 
+const UPLOAD_CHUNK_SIZE: usize = 256 * 1024;
+
 pub struct ApiClient {
     client: reqwest::Client,
     auth: Option<Auth>,
+    user_id: Option<UserId>,
     auth_manager: AuthManager,
 
     auth_service_api_url: String,
     user_service_api_url: String,
     message_service_api_url: String,
-    // write_message_ws: Option<WriteMessageWs>,
-    // read_message_ws: Option<ReadMessageWs>,
+    message_websocket_url: String,
 }
 
 impl ApiClient {
@@ -57,35 +318,57 @@ impl ApiClient {
         auth_service_api_url: String,
         user_service_api_url: String,
         message_service_api_url: String,
+        message_websocket_url: String,
         auth_manager: AuthManager,
     ) -> Self {
-        let obj = Self {
+        Self {
             client,
             auth: auth_manager.get_auth(),
+            user_id: auth_manager.get_user_id(),
             auth_manager,
 
             auth_service_api_url,
             user_service_api_url,
             message_service_api_url,
-            // write_message_ws: None,
-            // read_message_ws: None,
-        };
-
-        // if obj.auth_tokens.is_some() {
-        //     obj.connect_to_message_ws(RequestParams::default()).await;
-        // }
-
-        obj
+            message_websocket_url,
+        }
     }
 
     pub fn is_authenticated(&self) -> bool {
         self.auth.is_some()
     }
 
+    /// The message socket to connect to, it carries no credentials, see
+    /// [`Self::message_socket_auth`].
+    pub fn message_socket_url(&self) -> &str {
+        &self.message_websocket_url
+    }
+
+    /// First frame of a socket connection, `None` while logged out.
+    ///
+    /// Browsers can't set headers on a websocket, and a token in the url would end up in the
+    /// server's access logs, so the access token is sent over the connection.
+    pub fn message_socket_auth(&self) -> Option<String> {
+        let auth = self.auth.as_ref()?;
+        Some(encode_auth(&auth.access_token))
+    }
+
+    /// Gets new tokens outside of a request, e.g. after the socket was refused.
+    pub async fn refresh_auth(&mut self) -> ApiResult<()> {
+        if self.auth.is_none() {
+            return Err(ApiError::Unauthenticated);
+        }
+        let result = self.refresh_tokens(&mut RequestParams::default()).await;
+        if let Err(ApiError::Unauthenticated) = result {
+            self.log_out();
+        }
+        result
+    }
+
     pub async fn login(&mut self, login_req: LoginRequest) -> Result<AuthResponse, AuthError> {
         let res = self
             .client
-            .post(&self.auth_url("login"))
+            .post(self.auth_url("login"))
             .json(&login_req)
             .send()
             .await
@@ -109,6 +392,7 @@ impl ApiClient {
             &auth_response.access_token,
             &auth_response.refresh_token,
         ));
+        self.user_id = Some(auth_response.user_id.clone());
         Ok(auth_response)
     }
 
@@ -118,7 +402,7 @@ impl ApiClient {
     ) -> Result<AuthResponse, RegisterError> {
         let res = self
             .client
-            .post(&self.user_url("users"))
+            .post(self.user_url("users"))
             .json(&register_req)
             .send()
             .await
@@ -147,11 +431,12 @@ impl ApiClient {
             &auth_response.access_token,
             &auth_response.refresh_token,
         ));
+        self.user_id = Some(auth_response.user_id.clone());
         Ok(auth_response)
     }
-    
+
     pub async fn logout(&mut self) -> Result<(), AuthError> {
-         let refresh_token_data = schemas::RefreshTokenRequest {
+        let refresh_token_data = schemas::RefreshTokenRequest {
             refresh_token: self
                 .auth
                 .as_ref()
@@ -161,7 +446,7 @@ impl ApiClient {
         };
         let res = self
             .client
-            .post(&self.auth_url("logout"))
+            .post(self.auth_url("logout"))
             .json(&refresh_token_data)
             .header("Authorization", self.get_authorization_header())
             .send()
@@ -190,7 +475,7 @@ impl ApiClient {
             .await
             .map_err(|e| ApiError::DataError(e.to_string()))?;
 
-        Ok(serde_json::from_str(&data.to_string()).unwrap())
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
     }
 
     pub async fn get_chats(&mut self) -> ApiResult<schemas::ChatSearchResults> {
@@ -198,18 +483,13 @@ impl ApiClient {
             uri: self.message_url("chats"),
             ..Default::default()
         };
-        match self.get(rp).await {
-            Ok(res) => {
-                let data = res
-                    .json::<serde_json::Value>()
-                    .await
-                    .map_err(|e| ApiError::DataError(e.to_string()))?;
-                let chats: schemas::ChatSearchResults =
-                    serde_json::from_str(&data.to_string()).unwrap();
-                Ok(chats)
-            }
-            Err(e) => Err(e),
-        }
+        let res = self.get(rp).await?;
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
     }
 
     pub async fn get_chat(&mut self, chat_id: ChatId) -> ApiResult<schemas::ChatModel> {
@@ -221,21 +501,57 @@ impl ApiClient {
         let data = res
             .json::<serde_json::Value>()
             .await
-            .map_err(|e| e.to_string())
-            .expect("Failed to parse response");
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
 
-        Ok(serde_json::from_str(&data.to_string()).unwrap())
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
     }
 
-    pub async fn mark_chat_as_read(&mut self, chat_id: ChatId) {
+    /// A page of the chat's history before `before_seq`, or the latest messages without it.
+    pub async fn get_messages(
+        &mut self,
+        chat_id: ChatId,
+        before_seq: Option<u64>,
+        limit: u32,
+    ) -> ApiResult<schemas::MessagePage> {
+        let mut query_params = vec![("limit".to_string(), limit.to_string())];
+        if let Some(seq) = before_seq {
+            query_params.push(("before".to_string(), seq.to_string()));
+        }
+        let rp = RequestParams {
+            uri: self.message_url(&f!("chats/{chat_id}/messages")),
+            query_params,
+            ..Default::default()
+        };
+        let res = self.get(rp).await?;
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
+    }
+
+    pub async fn get_capabilities(&mut self) -> ApiResult<schemas::ServerCapabilities> {
+        let rp = RequestParams {
+            uri: self.message_url("capabilities"),
+            ..Default::default()
+        };
+        let res = self.get(rp).await?;
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
+    }
+
+    pub async fn mark_chat_as_read(&mut self, chat_id: ChatId) -> ApiResult<()> {
         let rp = RequestParams {
             uri: self.message_url(&f!("chats/{chat_id}/read")),
             ..Default::default()
         };
-        match self.post(rp).await {
-            Ok(_) => {}
-            Err(_) => {}
-        }
+        self.post(rp).await?;
+        Ok(())
     }
 
     pub async fn search_users(
@@ -252,21 +568,158 @@ impl ApiClient {
             .json::<serde_json::Value>()
             .await
             .map_err(|e| ApiError::DataError(e.to_string()))?;
-        let users: schemas::UserSearchResults = serde_json::from_str(&data.to_string()).unwrap();
 
-        Ok(users)
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
+    }
+
+    pub async fn send_message(
+        &mut self,
+        message: schemas::NewMessage,
+    ) -> ApiResult<schemas::MessageModel> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!("chats/{}/messages", message.chat_id)),
+            body: Some(serde_json::to_value(&message).unwrap()),
+            ..Default::default()
+        };
+        let res = self.post(rp).await?;
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
+    }
+
+    pub async fn edit_message(
+        &mut self,
+        chat_id: ChatId,
+        message_id: &MessageId,
+        text: String,
+    ) -> ApiResult<schemas::MessageModel> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!(
+                "chats/{chat_id}/messages/{}",
+                encode_path_segment(message_id)
+            )),
+            body: Some(serde_json::to_value(&schemas::EditMessageRequest { text }).unwrap()),
+            ..Default::default()
+        };
+        let res = self.patch(rp).await?;
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
     }
 
-    // pub async fn send_message(&mut self, message: schemas::NewMessage) {
-    //     let send_message = self
-    //         .write_message_ws
-    //         .as_mut()
-    //         .expect("Unauthenticated")
-    //         .send(Message::Text(Utf8Bytes::from(
-    //             serde_json::to_string(&message).unwrap(),
-    //         )));
-    //     send_message.await.expect("Failed to send message");
-    // }
+    pub async fn delete_message(
+        &mut self,
+        chat_id: ChatId,
+        message_id: &MessageId,
+        scope: schemas::DeleteScope,
+    ) -> ApiResult<()> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!(
+                "chats/{chat_id}/messages/{}",
+                encode_path_segment(message_id)
+            )),
+            query_params: vec![("scope".to_string(), scope.as_str().to_string())],
+            ..Default::default()
+        };
+        self.delete(rp).await?;
+        Ok(())
+    }
+
+    pub async fn add_reaction(
+        &mut self,
+        chat_id: ChatId,
+        message_id: &MessageId,
+        emoji: String,
+    ) -> ApiResult<()> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!(
+                "chats/{chat_id}/messages/{}/reactions",
+                encode_path_segment(message_id)
+            )),
+            body: Some(serde_json::to_value(&schemas::ReactionRequest { emoji }).unwrap()),
+            ..Default::default()
+        };
+        self.post(rp).await?;
+        Ok(())
+    }
+
+    pub async fn remove_reaction(
+        &mut self,
+        chat_id: ChatId,
+        message_id: &MessageId,
+        emoji: String,
+    ) -> ApiResult<()> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!(
+                "chats/{chat_id}/messages/{}/reactions",
+                encode_path_segment(message_id)
+            )),
+            query_params: vec![("emoji".to_string(), emoji)],
+            ..Default::default()
+        };
+        self.delete(rp).await?;
+        Ok(())
+    }
+
+    /// Sends `file.data[start..end]`, the response to the last chunk is the attachment.
+    ///
+    /// The last chunk also carries the dimensions and variants of an image.
+    async fn upload_chunk(
+        &mut self,
+        chat_id: ChatId,
+        upload_id: &str,
+        file: &schemas::FileUpload,
+        start: usize,
+        end: usize,
+    ) -> ApiResult<Option<schemas::Attachment>> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!("chats/{chat_id}/attachments/{upload_id}")),
+            ..Default::default()
+        };
+        let is_last = end == file.data.len();
+        let form = || {
+            let chunk = Part::bytes(file.data[start..end].to_vec()).file_name(file.name.clone());
+            let mut form = Form::new()
+                .text("name", file.name.clone())
+                .text("mime", file.mime.clone())
+                .text("size", file.data.len().to_string())
+                .text("offset", start.to_string())
+                .part("chunk", chunk);
+            if !is_last {
+                return form;
+            }
+            if let (Some(width), Some(height)) = (file.width, file.height) {
+                form = form
+                    .text("width", width.to_string())
+                    .text("height", height.to_string());
+            }
+            for variant in &file.variants {
+                let name = f!("{}x{}", variant.width, variant.height);
+                form = form.part("variant", Part::bytes(variant.data.clone()).file_name(name));
+            }
+            form
+        };
+        let res = self
+            .send(Method::POST, rp, |req| req.multipart(form()))
+            .await?;
+        if !is_last {
+            return Ok(None);
+        }
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data)
+            .map(Some)
+            .map_err(|e| ApiError::DataError(e.to_string()))
+    }
 
     pub async fn create_chat(
         &mut self,
@@ -282,52 +735,251 @@ impl ApiClient {
         let data = res
             .json::<serde_json::Value>()
             .await
-            .map_err(|e| e.to_string())
-            .expect("Failed to parse response");
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
         if !status.is_success() {
             return Err(ApiError::RequestError(data["detail"].to_string()));
         }
 
-        Ok(serde_json::from_str(&data.to_string()).unwrap())
-    }
-
-    // pub async fn receive_message(&mut self) -> Option<schemas::MessageModel> {
-    //     if let Some(message) = self
-    //         .read_message_ws
-    //         .as_mut()
-    //         .expect("Unauthenticated")
-    //         .next()
-    //         .await
-    //     {
-    //         match message {
-    //             Ok(Message::Text(text)) => {
-    //                 match serde_json::from_str::<schemas::MessageModel>(&text) {
-    //                     Ok(parsed_message) => {
-    //                         return Some(parsed_message);
-    //                     }
-    //                     Err(e) => {
-    //                         panic!("Failed to parse message: {}", e);
-    //                     }
-    //                 }
-    //             }
-    //             Ok(Message::Binary(_)) => {
-    //                 panic!("Received a binary message");
-    //             }
-    //             Ok(Message::Ping(_)) | Ok(Message::Pong(_)) => {}
-    //             Ok(Message::Close(_)) => {
-    //                 unimplemented!("Handle close messages, possibly clean up or reconnect");
-    //             }
-    //             Err(e) => {
-    //                 panic!("Failed to receive message: {}", e);
-    //             }
-    //             _ => {
-    //                 panic!("Received unexpected message type");
-    //             }
-    //         }
-    //     };
-    //
-    //     None
-    // }
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
+    }
+
+    /// Renames a group, `None` removes the name. Needs the admin role.
+    pub async fn rename_chat(
+        &mut self,
+        chat_id: ChatId,
+        name: Option<String>,
+    ) -> ApiResult<schemas::ChatModel> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!("chats/{chat_id}")),
+            body: Some(serde_json::to_value(&schemas::RenameChatRequest { name }).unwrap()),
+            ..Default::default()
+        };
+        let res = self.patch(rp).await?;
+        Self::parse_chat(res).await
+    }
+
+    /// Needs the admin role.
+    pub async fn add_members(
+        &mut self,
+        chat_id: ChatId,
+        user_ids: Vec<UserId>,
+    ) -> ApiResult<schemas::ChatModel> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!("chats/{chat_id}/members")),
+            body: Some(serde_json::to_value(&schemas::AddMembersRequest { user_ids }).unwrap()),
+            ..Default::default()
+        };
+        let res = self.post(rp).await?;
+        Self::parse_chat(res).await
+    }
+
+    /// Needs a role above the member's.
+    pub async fn remove_member(
+        &mut self,
+        chat_id: ChatId,
+        user_id: &UserId,
+    ) -> ApiResult<schemas::ChatModel> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!(
+                "chats/{chat_id}/members/{}",
+                encode_path_segment(user_id)
+            )),
+            ..Default::default()
+        };
+        let res = self.delete(rp).await?;
+        Self::parse_chat(res).await
+    }
+
+    /// Only the owner can change roles.
+    pub async fn set_member_role(
+        &mut self,
+        chat_id: ChatId,
+        user_id: &UserId,
+        role: schemas::ChatRole,
+    ) -> ApiResult<schemas::ChatModel> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!(
+                "chats/{chat_id}/members/{}",
+                encode_path_segment(user_id)
+            )),
+            body: Some(serde_json::to_value(&schemas::UpdateMemberRequest { role }).unwrap()),
+            ..Default::default()
+        };
+        let res = self.patch(rp).await?;
+        Self::parse_chat(res).await
+    }
+
+    pub async fn leave_chat(&mut self, chat_id: ChatId) -> ApiResult<()> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!("chats/{chat_id}/leave")),
+            ..Default::default()
+        };
+        self.post(rp).await?;
+        Ok(())
+    }
+
+    /// Saves the current user's settings for the chat, e.g. pinning it.
+    pub async fn update_chat_settings(
+        &mut self,
+        chat_id: ChatId,
+        settings: &schemas::ChatSettings,
+    ) -> ApiResult<schemas::ChatSettings> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!("chats/{chat_id}/settings")),
+            body: Some(serde_json::to_value(settings).unwrap()),
+            ..Default::default()
+        };
+        let res = self.put(rp).await?;
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
+    }
+
+    /// Messages of all the user's chats matching the query, most recent first.
+    pub async fn search_messages(
+        &mut self,
+        query: String,
+        limit: u32,
+    ) -> ApiResult<schemas::MessageSearchResults> {
+        let rp = RequestParams {
+            uri: self.message_url("messages/search"),
+            query_params: vec![
+                ("q".to_string(), query),
+                ("limit".to_string(), limit.to_string()),
+            ],
+            ..Default::default()
+        };
+        let res = self.get(rp).await?;
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
+    }
+
+    pub async fn get_folders(&mut self) -> ApiResult<schemas::ChatFolders> {
+        let rp = RequestParams {
+            uri: self.message_url("folders"),
+            ..Default::default()
+        };
+        let res = self.get(rp).await?;
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
+    }
+
+    /// Creates or replaces the folder, the id is chosen by the client.
+    pub async fn save_folder(
+        &mut self,
+        folder: &schemas::ChatFolder,
+    ) -> ApiResult<schemas::ChatFolder> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!("folders/{}", encode_path_segment(&folder.id))),
+            body: Some(serde_json::to_value(folder).unwrap()),
+            ..Default::default()
+        };
+        let res = self.put(rp).await?;
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
+    }
+
+    pub async fn delete_folder(&mut self, folder_id: &str) -> ApiResult<()> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!("folders/{}", encode_path_segment(folder_id))),
+            ..Default::default()
+        };
+        self.delete(rp).await?;
+        Ok(())
+    }
+
+    /// Invites of the chat that can still be used. Needs the admin role.
+    pub async fn get_invites(&mut self, chat_id: ChatId) -> ApiResult<schemas::ChatInvites> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!("chats/{chat_id}/invites")),
+            ..Default::default()
+        };
+        let res = self.get(rp).await?;
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
+    }
+
+    /// Needs the admin role.
+    pub async fn create_invite(
+        &mut self,
+        chat_id: ChatId,
+        invite: schemas::NewChatInvite,
+    ) -> ApiResult<schemas::ChatInvite> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!("chats/{chat_id}/invites")),
+            body: Some(serde_json::to_value(&invite).unwrap()),
+            ..Default::default()
+        };
+        let res = self.post(rp).await?;
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
+    }
+
+    pub async fn revoke_invite(&mut self, chat_id: ChatId, code: &str) -> ApiResult<()> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!("chats/{chat_id}/invites/{}", encode_path_segment(code))),
+            ..Default::default()
+        };
+        self.delete(rp).await?;
+        Ok(())
+    }
+
+    /// Fails for codes that are unknown, expired or used up.
+    pub async fn get_invite(&mut self, code: &str) -> ApiResult<schemas::InvitePreview> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!("invites/{}", encode_path_segment(code))),
+            ..Default::default()
+        };
+        let res = self.get(rp).await?;
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
+    }
+
+    /// Joins the chat of the invite, returns it with the new member.
+    pub async fn join_chat(&mut self, code: &str) -> ApiResult<schemas::ChatModel> {
+        let rp = RequestParams {
+            uri: self.message_url(&f!("invites/{}/join", encode_path_segment(code))),
+            ..Default::default()
+        };
+        let res = self.post(rp).await?;
+        Self::parse_chat(res).await
+    }
+
+    async fn parse_chat(res: Response) -> ApiResult<schemas::ChatModel> {
+        let data = res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))?;
+
+        serde_json::from_value(data).map_err(|e| ApiError::DataError(e.to_string()))
+    }
 
     async fn post(&mut self, mut rp: RequestParams) -> ApiResult<Response> {
         loop {
@@ -362,6 +1014,67 @@ impl ApiClient {
         }
     }
 
+    async fn patch(&mut self, rp: RequestParams) -> ApiResult<Response> {
+        self.request(Method::PATCH, rp).await
+    }
+
+    async fn put(&mut self, rp: RequestParams) -> ApiResult<Response> {
+        self.request(Method::PUT, rp).await
+    }
+
+    async fn delete(&mut self, rp: RequestParams) -> ApiResult<Response> {
+        self.request(Method::DELETE, rp).await
+    }
+
+    /// Same as `post`, for the other methods, the body is only sent if there is one.
+    async fn request(&mut self, method: Method, rp: RequestParams) -> ApiResult<Response> {
+        let body = rp.body.clone();
+        self.send(method, rp, |req| match &body {
+            Some(body) => req.json(body),
+            None => req,
+        })
+        .await
+    }
+
+    /// Sends the request, the body is attached again when it's retried after a token refresh.
+    async fn send(
+        &mut self,
+        method: Method,
+        mut rp: RequestParams,
+        body: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> ApiResult<Response> {
+        loop {
+            let url = Url::parse_with_params(&rp.uri, rp.query_params.clone()).unwrap();
+            let req = self
+                .client
+                .request(method.clone(), url)
+                .header("Authorization", self.get_authorization_header());
+            let res = body(req)
+                .send()
+                .await
+                .map_err(|e| ApiError::RequestError(e.to_string()))?;
+
+            if self.should_refresh_tokens(&mut rp, &res) {
+                match self.refresh_tokens(&mut rp).await {
+                    Ok(_) => continue,
+                    Err(e) => {
+                        self.log_out();
+                        return Err(e);
+                    }
+                }
+            }
+            if !res.status().is_success() {
+                let data = res
+                    .json::<serde_json::Value>()
+                    .await
+                    .map_err(|e| ApiError::DataError(e.to_string()))?;
+                return Err(ApiError::RequestError(data["detail"].to_string()));
+            }
+
+            return Ok(res);
+        }
+    }
+
     fn should_refresh_tokens(&mut self, rp: &mut RequestParams, res: &Response) -> bool {
         res.status() == StatusCode::UNAUTHORIZED && rp.can_reauthenticate && self.auth.is_some()
     }
@@ -414,7 +1127,7 @@ impl ApiClient {
         };
         let res = self
             .client
-            .post(&self.auth_url("refresh-token"))
+            .post(self.auth_url("refresh-token"))
             .json(&refresh_token_data)
             .send()
             .await
@@ -445,54 +1158,6 @@ impl ApiClient {
         Ok(())
     }
 
-    // async fn connect_to_message_ws(&mut self, mut rp: RequestParams) {
-    //     loop {
-    //         let request = Request::builder()
-    //             .uri(MESSAGE_WEBSOCKET_URL)
-    //             .header(AUTHORIZATION, self.get_authorization_header())
-    //             .header("sec-websocket-key", helpers::generate_sec_websocket_key())
-    //             .header("host", HOST)
-    //             .header("upgrade", "websocket")
-    //             .header("connection", "upgrade")
-    //             .header("sec-websocket-version", 13)
-    //             .body(())
-    //             .expect("Failed to build request.");
-    //
-    //         match connect_async(request).await {
-    //             Ok((ws_stream, _)) => {
-    //                 let (write_ws, read_ws) = ws_stream.split();
-    //                 self.write_message_ws = Some(write_ws);
-    //                 self.read_message_ws = Some(read_ws);
-    //                 break;
-    //             }
-    //             Err(Error::Http(response)) => {
-    //                 if response.status() == tungstenite::http::StatusCode::UNAUTHORIZED
-    //                     && rp.can_reauthenticate
-    //                 {
-    //                     if self.auth_tokens.is_some() {
-    //                         match self.refresh_tokens(&mut rp).await {
-    //                             Ok(_) => continue,
-    //                             Err(_) => {
-    //                                 self.unauthenticate();
-    //                                 return;
-    //                             }
-    //                         }
-    //                     } else {
-    //                         panic!("Can't connect to ws: Unauthenticated");
-    //                     }
-    //                 }
-    //                 panic!(
-    //                     "Failed to connect to message websocket: {}",
-    //                     response.status()
-    //                 );
-    //             }
-    //             Err(e) => {
-    //                 panic!("Failed to connect to message websocket: {}", e);
-    //             }
-    //         }
-    //     }
-    // }
-
     fn set_auth_tokens(&mut self, tokens: Auth) {
         self.auth = Some(tokens.clone());
         self.auth_manager.update_auth(tokens);
@@ -500,6 +1165,7 @@ impl ApiClient {
 
     fn log_out(&mut self) {
         self.auth = None;
+        self.user_id = None;
     }
 
     fn get_authorization_header(&mut self) -> String {
@@ -528,7 +1194,7 @@ impl ApiClient {
     }
 }
 
-type ApiResult<T> = Result<T, ApiError>;
+pub type ApiResult<T> = Result<T, ApiError>;
 
 #[derive(Debug, Clone)]
 pub enum ApiError {
@@ -14,6 +14,7 @@ fn get_api_client(storage: SharedStorage) -> ApiClient {
         config.auth_service_api_url.clone(),
         config.user_service_api_url.clone(),
         config.message_service_api_url.clone(),
+        config.message_websocket_url.clone(),
         auth::factory::get_auth_manager(storage),
     )
 }
@@ -1,3 +1,4 @@
 pub mod client;
 pub mod factory;
 pub mod schemas;
+pub mod socket;
@@ -1,4 +1,4 @@
-use crate::helpers::types::{ChatId, UserId};
+use crate::helpers::types::{ChatId, MessageId, UserId};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use validator::{Validate, ValidationError};
@@ -51,55 +51,583 @@ pub struct RefreshTokenRequest {
     pub refresh_token: String,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ChatModel {
     pub id: ChatId,
     pub name: Option<String>,
     pub member_ids: Vec<String>,
     pub messages: Vec<MessageModel>,
+    /// Seq of the last message each member has read.
+    #[serde(default)]
+    pub read_watermarks: HashMap<UserId, u64>,
+    /// Members with a role above `ChatRole::Member`.
+    #[serde(default)]
+    pub roles: HashMap<UserId, ChatRole>,
+    /// The current user's own settings for the chat.
+    #[serde(default)]
+    pub settings: ChatSettings,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+/// How the chat is listed for the current user, the other members don't see it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ChatSettings {
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub muted: bool,
+    /// When the mute ends. `None` mutes until unmuted.
+    #[serde(default)]
+    pub muted_until: Option<f64>,
+}
+
+impl ChatSettings {
+    pub fn is_muted(&self, now: f64) -> bool {
+        self.muted && self.muted_until.is_none_or(|until| now < until)
+    }
+}
+
+/// A tab of the chat list with the chats the user has put in it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChatFolder {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub chat_ids: Vec<ChatId>,
+    /// Also holds every chat with unread messages.
+    #[serde(default)]
+    pub include_unread: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChatFolders {
+    pub folders: Vec<ChatFolder>,
+}
+
+/// Ordered by what the role is allowed to do.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatRole {
+    #[default]
+    Member,
+    Admin,
+    Owner,
+}
+
+impl ChatRole {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChatRole::Member => "Member",
+            ChatRole::Admin => "Admin",
+            ChatRole::Owner => "Owner",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RenameChatRequest {
+    pub name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AddMembersRequest {
+    pub user_ids: Vec<UserId>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UpdateMemberRequest {
+    pub role: ChatRole,
+}
+
+/// A code that lets anyone who has it join the chat, shared as a link.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChatInvite {
+    pub code: String,
+    pub chat_id: ChatId,
+    pub created_by: UserId,
+    pub created_at: f64,
+    #[serde(default)]
+    pub expires_at: Option<f64>,
+    #[serde(default)]
+    pub max_uses: Option<u32>,
+    #[serde(default)]
+    pub uses: u32,
+}
+
+impl ChatInvite {
+    /// Not expired and not used up, the server makes the same check when it's used.
+    pub fn is_active(&self, now: f64) -> bool {
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+            && self.max_uses.is_none_or(|max_uses| self.uses < max_uses)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct NewChatInvite {
+    /// Seconds until the invite expires. `None` never expires.
+    pub expires_in: Option<u64>,
+    pub max_uses: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChatInvites {
+    pub invites: Vec<ChatInvite>,
+}
+
+/// What someone with the code sees of the chat before joining it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InvitePreview {
+    pub code: String,
+    pub chat_id: ChatId,
+    pub name: Option<String>,
+    pub member_count: u32,
+    #[serde(default)]
+    pub expires_at: Option<f64>,
+}
+
+/// What a system message in the timeline reports, the sender is who did it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SystemEvent {
+    Created,
+    Renamed { name: Option<String> },
+    MembersAdded { user_ids: Vec<UserId> },
+    MemberRemoved { user_id: UserId },
+    MemberLeft,
+    RoleChanged { user_id: UserId, role: ChatRole },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct NewChatModel {
     pub name: Option<String>,
     pub member_ids: Vec<String>,
     pub first_message: String,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct NewMessage {
     // todo shouldn't this also contain created at and is_read? then it could be the single model
     pub chat_id: ChatId,
     pub sender_id: String,
     pub text: String,
+    /// Idempotency key, a retried send with the same key doesn't create a second message.
+    pub client_id: String,
+    #[serde(default)]
+    pub reply_to: Option<MessageQuote>,
+    /// Uploaded with [`crate::api::client::SharedApiClient::upload_attachment`] beforehand.
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+impl NewMessage {
+    pub fn new(chat_id: ChatId, sender_id: UserId, text: String) -> Self {
+        Self {
+            chat_id,
+            sender_id,
+            text,
+            client_id: uuid::Uuid::new_v4().to_string(),
+            reply_to: None,
+            attachments: vec![],
+        }
+    }
+
+    pub fn with_reply_to(mut self, reply_to: Option<MessageQuote>) -> Self {
+        self.reply_to = reply_to;
+        self
+    }
+
+    pub fn with_attachments(mut self, attachments: Vec<Attachment>) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
+    /// Local copy shown until the server acknowledges the message, ordered after every
+    /// acknowledged one.
+    pub fn to_pending(&self, created_at: f64) -> MessageModel {
+        MessageModel {
+            id: self.client_id.clone(),
+            seq: u64::MAX,
+            client_id: Some(self.client_id.clone()),
+            chat_id: self.chat_id,
+            sender_id: self.sender_id.clone(),
+            text: self.text.clone(),
+            created_at,
+            is_read: false,
+            status: DeliveryStatus::Pending,
+            edited_at: None,
+            edits: vec![],
+            deleted: false,
+            reply_to: self.reply_to.clone(),
+            reactions: vec![],
+            attachments: self.attachments.clone(),
+            system: None,
+        }
+    }
+}
+
+/// Delivery state of an outgoing message.
+///
+/// `Pending` and `Failed` only exist on the client, the server reports the others. A message
+/// only moves forward, except for `Failed`, which goes back to `Pending` on retry.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    #[default]
+    Sent,
+    Delivered,
+    Read,
+    Failed,
+}
+
+impl DeliveryStatus {
+    fn rank(self) -> u8 {
+        match self {
+            DeliveryStatus::Pending | DeliveryStatus::Failed => 0,
+            DeliveryStatus::Sent => 1,
+            DeliveryStatus::Delivered => 2,
+            DeliveryStatus::Read => 3,
+        }
+    }
+
+    /// Status after an update, so that late or repeated updates can't move it back.
+    pub fn merge(self, update: DeliveryStatus) -> DeliveryStatus {
+        if update.rank() >= self.rank() {
+            update
+        } else {
+            self
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct MessageModel {
-    pub chat_id: u32,
+    pub id: MessageId,
+    /// Position in the chat, assigned by the server and increasing without gaps.
+    pub seq: u64,
+    /// Idempotency key of the sent message, only known for messages sent by this user.
+    #[serde(default)]
+    pub client_id: Option<String>,
+    pub chat_id: ChatId,
     pub sender_id: String,
     pub text: String,
     pub created_at: f64,
     pub is_read: bool,
+    #[serde(default)]
+    pub status: DeliveryStatus,
+    #[serde(default)]
+    pub edited_at: Option<f64>,
+    /// Previous versions, oldest first.
+    #[serde(default)]
+    pub edits: Vec<MessageEdit>,
+    /// Deleted for everyone, the text is gone and only a tombstone is left.
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub reply_to: Option<MessageQuote>,
+    #[serde(default)]
+    pub reactions: Vec<Reaction>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    /// Set on the messages the server adds for changes to the chat, e.g. a new member.
+    #[serde(default)]
+    pub system: Option<SystemEvent>,
+}
+
+impl MessageModel {
+    /// The same message to send again, for messages that failed to send.
+    pub fn to_retry(&self) -> Option<NewMessage> {
+        Some(NewMessage {
+            chat_id: self.chat_id,
+            sender_id: self.sender_id.clone(),
+            text: self.text.clone(),
+            client_id: self.client_id.clone()?,
+            reply_to: self.reply_to.clone(),
+            attachments: self.attachments.clone(),
+        })
+    }
+
+    pub fn to_quote(&self) -> MessageQuote {
+        MessageQuote {
+            id: self.id.clone(),
+            seq: self.seq,
+            sender_id: self.sender_id.clone(),
+            text: self.text.clone(),
+        }
+    }
+}
+
+/// The message replied to, as it was when the reply was sent.
+///
+/// The `seq` tells how far back the history has to be loaded to reach the original.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MessageQuote {
+    pub id: MessageId,
+    pub seq: u64,
+    pub sender_id: UserId,
+    pub text: String,
+}
+
+/// An uploaded file, the dimensions are only known for images.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Attachment {
+    pub id: String,
+    pub name: String,
+    pub size: u64,
+    pub mime: String,
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub height: Option<u32>,
+    pub url: String,
+    /// Downscaled copies of an image, smallest first.
+    #[serde(default)]
+    pub variants: Vec<ImageVariant>,
+}
+
+impl Attachment {
+    pub fn is_image(&self) -> bool {
+        self.mime.starts_with("image/")
+    }
+
+    /// The smallest copy that is at least `width` wide, to save loading the full image.
+    pub fn preview_url(&self, width: u32) -> &str {
+        self.variants
+            .iter()
+            .find(|v| v.width >= width)
+            .map_or(&self.url, |v| &v.url)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ImageVariant {
+    pub width: u32,
+    pub height: u32,
+    pub url: String,
+}
+
+/// A file read into memory, ready to be uploaded.
+///
+/// The dimensions and variants of images are filled in by [`crate::media::prepare_upload`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileUpload {
+    pub name: String,
+    pub mime: String,
+    pub data: Vec<u8>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub variants: Vec<EncodedImage>,
+}
+
+impl FileUpload {
+    pub fn new(name: String, mime: String, data: Vec<u8>) -> Self {
+        Self {
+            name,
+            mime,
+            data,
+            width: None,
+            height: None,
+            variants: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncodedImage {
+    pub mime: String,
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Limits of the message service, the defaults apply to servers that don't report them.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ServerCapabilities {
+    pub max_upload_size: u64,
+    /// Larger images are downscaled to fit before they are uploaded.
+    pub max_image_dimension: u32,
+    /// Bounding boxes of the downscaled variants uploaded with an image, e.g. thumbnails.
+    pub image_variant_sizes: Vec<u32>,
+}
+
+impl Default for ServerCapabilities {
+    fn default() -> Self {
+        Self {
+            max_upload_size: 25 * 1024 * 1024,
+            max_image_dimension: 2560,
+            image_variant_sizes: vec![320, 1280],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UploadProgress {
+    pub sent: u64,
+    pub total: u64,
+}
+
+impl UploadProgress {
+    /// Between 0 and 1, an empty file is done right away.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.sent as f64 / self.total as f64
+        }
+    }
+}
+
+/// Reactions to a message with the same emoji.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Reaction {
+    pub emoji: String,
+    pub count: u32,
+    pub user_ids: Vec<UserId>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReactionRequest {
+    pub emoji: String,
+}
+
+/// A user adding or removing a reaction.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReactionUpdate {
+    pub chat_id: ChatId,
+    pub message_id: MessageId,
+    pub user_id: UserId,
+    pub emoji: String,
+    pub added: bool,
+}
+
+/// Messages older than the `before` seq of the request, oldest first.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MessagePage {
+    pub messages: Vec<MessageModel>,
+    pub has_more: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MessageEdit {
+    pub text: String,
+    pub edited_at: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EditMessageRequest {
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteScope {
+    Everyone,
+    /// Hides the message for the current user only.
+    Me,
+}
+
+impl DeleteScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeleteScope::Everyone => "everyone",
+            DeleteScope::Me => "me",
+        }
+    }
+}
+
+/// The recipients got, or read, every message of the sender up to `seq`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DeliveryReceipt {
+    pub chat_id: ChatId,
+    pub seq: u64,
+    pub status: DeliveryStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceStatus {
+    Online,
+    Away,
+    #[default]
+    Offline,
+}
+
+/// Whether a user is around, `last_seen` is when they were last online.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Presence {
+    pub user_id: UserId,
+    pub status: PresenceStatus,
+    #[serde(default)]
+    pub last_seen: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TypingUpdate {
+    pub chat_id: ChatId,
+    pub user_id: UserId,
+    pub typing: bool,
+}
+
+/// The member has read every message of the chat up to `seq`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReadWatermark {
+    pub chat_id: ChatId,
+    pub user_id: UserId,
+    pub seq: u64,
+}
+
+/// Events pushed by the message service over the websocket.
+///
+/// Edits and deletions for everyone arrive as the updated message.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum WsEvent {
+    Message(Box<MessageModel>),
+    Chat(ChatModel),
+    Receipt(DeliveryReceipt),
+    Reaction(ReactionUpdate),
+    /// Deleted for the current user only, e.g. from another device.
+    MessageHidden {
+        chat_id: ChatId,
+        message_id: MessageId,
+    },
+    Presence(Presence),
+    Typing(TypingUpdate),
+    Read(ReadWatermark),
+}
+
+/// Events sent to the message service over the websocket.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum WsCommand {
+    Typing { chat_id: ChatId, typing: bool },
+    Presence { status: PresenceStatus },
 }
 
 // todo maybe you should separate api schema and actual models
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct User {
     pub id: String,
     pub username: String,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct UserSearchResults {
     pub users: Vec<User>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ChatSearchResults {
     pub chats: Vec<ChatModel>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MessageSearchResults {
+    pub messages: Vec<MessageModel>,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct GetUsersByIdsRequest {
     pub user_ids: Vec<UserId>,
@@ -0,0 +1,120 @@
+//! Frames and reconnects of the message service socket, the connection itself is the platform's.
+
+use crate::api::schemas::{WsCommand, WsEvent};
+use std::time::Duration;
+
+/// Wait after the first failed attempt, doubled after every further one.
+const FIRST_RETRY: Duration = Duration::from_secs(1);
+const MAX_RETRY: Duration = Duration::from_secs(30);
+
+/// Close code of the service when the first frame isn't a valid [`encode_auth`] frame, e.g.
+/// because the access token has expired.
+pub const UNAUTHORIZED: u16 = 4001;
+
+/// The first frame of every connection.
+pub fn encode_auth(access_token: &str) -> String {
+    serde_json::json!({"type": "auth", "data": {"token": access_token}}).to_string()
+}
+
+pub fn decode_event(frame: &str) -> Result<WsEvent, serde_json::Error> {
+    serde_json::from_str(frame)
+}
+
+pub fn encode_command(command: &WsCommand) -> String {
+    serde_json::to_string(command).expect("Failed to serialize command")
+}
+
+/// Decides how long to wait before connecting again.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    failures: u32,
+}
+
+impl Backoff {
+    /// No wait after a connection that worked, e.g. one the server has restarted.
+    pub fn delay(&self) -> Duration {
+        match self.failures {
+            0 => Duration::ZERO,
+            n => FIRST_RETRY
+                .saturating_mul(2u32.saturating_pow(n - 1))
+                .min(MAX_RETRY),
+        }
+    }
+
+    pub fn on_connected(&mut self) {
+        self.failures = 0;
+    }
+
+    pub fn on_failure(&mut self) {
+        self.failures = self.failures.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::schemas::{DeliveryReceipt, DeliveryStatus, PresenceStatus};
+
+    #[test]
+    fn test_auth_frame() {
+        assert_eq!(
+            encode_auth("abc"),
+            r#"{"data":{"token":"abc"},"type":"auth"}"#
+        );
+    }
+
+    #[test]
+    fn test_frames_use_the_tagged_format() {
+        let event = decode_event(
+            r#"{"type": "receipt", "data": {"chat_id": 1, "seq": 5, "status": "delivered"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            event,
+            WsEvent::Receipt(DeliveryReceipt {
+                chat_id: 1,
+                seq: 5,
+                status: DeliveryStatus::Delivered,
+            })
+        );
+        assert!(decode_event(r#"{"type": "unknown", "data": {}}"#).is_err());
+
+        let command = encode_command(&WsCommand::Typing {
+            chat_id: 1,
+            typing: true,
+        });
+        assert_eq!(
+            command,
+            r#"{"type":"typing","data":{"chat_id":1,"typing":true}}"#
+        );
+        let command = WsCommand::Presence {
+            status: PresenceStatus::Online,
+        };
+        assert_eq!(
+            serde_json::from_str::<WsCommand>(&encode_command(&command)).unwrap(),
+            command
+        );
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_limit() {
+        let mut backoff = Backoff::default();
+        assert_eq!(backoff.delay(), Duration::ZERO);
+
+        let delays: Vec<u64> = (0..7)
+            .map(|_| {
+                backoff.on_failure();
+                backoff.delay().as_secs()
+            })
+            .collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 30, 30]);
+
+        for _ in 0..100 {
+            backoff.on_failure();
+        }
+        assert_eq!(backoff.delay(), MAX_RETRY);
+
+        backoff.on_connected();
+        assert_eq!(backoff.delay(), Duration::ZERO);
+    }
+}
@@ -1,4 +1,5 @@
 use serde::Deserialize;
+use std::collections::HashMap;
 
 pub const CORE_CONFIG_TOML: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/config.toml"));
@@ -16,6 +17,10 @@ pub struct CoreConfig {
     pub message_websocket_url: String,
 
     pub apps: Apps,
+
+    /// Key combos by scope and action, overriding the defaults of the shell and the apps.
+    #[serde(default)]
+    pub keybindings: HashMap<String, HashMap<String, Vec<String>>>,
 }
 
 #[derive(Debug, Deserialize)]
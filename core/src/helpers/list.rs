@@ -1,9 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use crate::helpers::traits::InternalID;
 
+/// Selection and visible window of a list.
+///
+/// The window shows `height` rows starting at `offset`, a height of 0 means that everything
+/// is visible.
 #[derive(Clone, Debug, Default)]
 pub struct ListState {
     offset: usize,
+    height: usize,
     selected: Option<usize>,
 }
 
@@ -15,8 +21,61 @@ impl ListState {
     pub fn selected(&self) -> Option<usize> {
         self.selected
     }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn set_height(&mut self, height: usize, len: usize) {
+        self.height = height;
+        self.clamp(len);
+    }
+
+    pub fn set_offset(&mut self, offset: usize, len: usize) {
+        self.offset = offset;
+        self.clamp(len);
+    }
+
+    /// Indices of the rows in the window.
+    pub fn visible_range(&self, len: usize) -> Range<usize> {
+        if self.height == 0 {
+            return 0..len;
+        }
+        let start = self.offset.min(len);
+        start..(start + self.height).min(len)
+    }
+
+    /// Moves the window as little as possible to show the row.
+    pub fn scroll_to(&mut self, index: usize) {
+        if self.height == 0 {
+            return;
+        }
+        if index < self.offset {
+            self.offset = index;
+        } else if index >= self.offset + self.height {
+            self.offset = index + 1 - self.height;
+        }
+    }
+
+    /// Keeps the window inside the list, so it's never partly empty.
+    fn clamp(&mut self, len: usize) {
+        if self.height == 0 {
+            self.offset = 0;
+        } else {
+            self.offset = self.offset.min(len.saturating_sub(self.height));
+        }
+    }
 }
 
+/// Items sorted in descending order, with O(1) lookup by `internal_id()`.
+///
+/// Ids are unique: inserting an item with a known id replaces it. Single item changes find
+/// the new position with a binary search, but still shift and re-index the items between the
+/// old and the new position, so they are O(n) when e.g. the last chat moves to the front.
 #[derive(Debug, Clone)]
 pub struct StatefulOrderedList<T>
     where T: InternalID + Ord + Clone
@@ -30,30 +89,114 @@ pub struct StatefulOrderedList<T>
 impl<T> StatefulOrderedList<T>
     where T: InternalID + Ord + Clone
 {
-    pub fn get(&self, item_id: &String) -> &T {
-        &self.item_indices.get(item_id).map(|i| &self.items[*i]).expect("Item not found")
+    pub fn get(&self, item_id: &str) -> &T {
+        self.find(item_id).expect("Item not found")
     }
 
-    pub fn get_mut(&mut self, item_id: &String) -> &mut T {
-        self.item_indices.get_mut(item_id).map(|i| &mut self.items[*i]).expect("Item not found")
+    pub fn get_mut(&mut self, item_id: &str) -> &mut T {
+        self.item_indices.get(item_id).map(|i| &mut self.items[*i]).expect("Item not found")
     }
 
-    pub fn contains(&self, item_id: &String) -> bool {
+    pub fn find(&self, item_id: &str) -> Option<&T> {
+        self.item_indices.get(item_id).map(|i| &self.items[*i])
+    }
+
+    pub fn position(&self, item_id: &str) -> Option<usize> {
+        self.item_indices.get(item_id).copied()
+    }
+
+    pub fn contains(&self, item_id: &str) -> bool {
         self.item_indices.contains_key(item_id)
     }
 
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Upserts every item and sorts once, later items win over earlier ones with the same id.
     pub fn extend(&mut self, items: Vec<T>) {
-        self.items.extend(items);
+        for item in items {
+            match self.item_indices.get(&item.internal_id()) {
+                Some(i) => self.items[*i] = item,
+                None => {
+                    self.item_indices.insert(item.internal_id(), self.items.len());
+                    self.items.push(item);
+                }
+            }
+        }
         self.update_order();
     }
 
     pub fn push(&mut self, item: T) {
-        self.items.push(item);
-        self.update_order();
+        self.insert(item);
+    }
+
+    /// Inserts the item at its position, or replaces and re-positions the item with the same
+    /// id, returning the old one.
+    pub fn insert(&mut self, item: T) -> Option<T> {
+        let id = item.internal_id();
+        match self.item_indices.get(&id).copied() {
+            Some(i) => {
+                let old = std::mem::replace(&mut self.items[i], item);
+                self.reposition(i);
+                Some(old)
+            }
+            None => {
+                let position = self.items.partition_point(|x| x > &item);
+                self.items.insert(position, item);
+                self.reindex(position, self.items.len() - 1);
+                self.update_state();
+                None
+            }
+        }
+    }
+
+    pub fn upsert(&mut self, item: T) -> Option<T> {
+        self.insert(item)
     }
 
-    pub fn select(&mut self, item_id: &String) {
-        self.selected_item_id = Some(item_id.clone());
+    pub fn remove(&mut self, item_id: &str) -> Option<T> {
+        let i = self.item_indices.remove(item_id)?;
+        let item = self.items.remove(i);
+        if i < self.items.len() {
+            self.reindex(i, self.items.len() - 1);
+        }
+        if self.selected_item_id.as_deref() == Some(item_id) {
+            self.unselect();
+        } else {
+            self.update_state();
+        }
+        Some(item)
+    }
+
+    /// Changes the item in place and moves it to its new position, returns false if there
+    /// is no item with that id. A change to the id of another item is undone, so ids stay
+    /// unique, and also returns false.
+    pub fn update_with(&mut self, item_id: &str, f: impl FnOnce(&mut T)) -> bool {
+        let Some(i) = self.position(item_id) else {
+            return false;
+        };
+        let before = self.items[i].clone();
+        f(&mut self.items[i]);
+        let new_id = self.items[i].internal_id();
+        if new_id != item_id {
+            if self.contains(&new_id) {
+                self.items[i] = before;
+                return false;
+            }
+            // the id has changed with the item
+            self.item_indices.remove(item_id);
+            if self.selected_item_id.as_deref() == Some(item_id) {
+                self.selected_item_id = Some(new_id.clone());
+            }
+            self.item_indices.insert(new_id, i);
+        }
+        self.reposition(i);
+        true
+    }
+
+    pub fn select(&mut self, item_id: &str) {
+        self.selected_item_id = Some(item_id.to_string());
         self.update_state();
     }
 
@@ -76,8 +219,7 @@ impl<T> StatefulOrderedList<T>
             }
             None => 0,
         };
-        self.state.select(Some(i));
-        self.selected_item_id = Some(self.items[i].internal_id());
+        self.select_index(i);
     }
 
     pub fn previous(&mut self) {
@@ -94,16 +236,70 @@ impl<T> StatefulOrderedList<T>
             }
             None => 0,
         };
-        self.state.select(Some(i));
-        self.selected_item_id = Some(self.items[i].internal_id());
+        self.select_index(i);
+    }
+
+    /// Moves the selection down by a window height and scrolls along.
+    pub fn page_down(&mut self) {
+        if self.is_empty() {
+            return;
+        }
+        let step = self.state.height().max(1);
+        let Some(i) = self.state.selected() else {
+            self.select_index(self.state.visible_range(self.items.len()).start);
+            return;
+        };
+        let i = (i + step).min(self.items.len() - 1);
+        let offset = self.state.offset() + step;
+        self.state.set_offset(offset, self.items.len());
+        self.select_index(i);
+    }
+
+    /// Moves the selection up by a window height and scrolls along.
+    pub fn page_up(&mut self) {
+        if self.is_empty() {
+            return;
+        }
+        let step = self.state.height().max(1);
+        let Some(i) = self.state.selected() else {
+            self.select_index(self.state.visible_range(self.items.len()).start);
+            return;
+        };
+        let i = i.saturating_sub(step);
+        let offset = self.state.offset().saturating_sub(step);
+        self.state.set_offset(offset, self.items.len());
+        self.select_index(i);
+    }
+
+    /// Scrolls the window to show the item, without selecting it.
+    pub fn scroll_to_item(&mut self, item_id: &str) -> bool {
+        let Some(i) = self.position(item_id) else {
+            return false;
+        };
+        self.state.scroll_to(i);
+        true
+    }
+
+    pub fn set_viewport_height(&mut self, height: usize) {
+        self.state.set_height(height, self.items.len());
+        if let Some(i) = self.state.selected() {
+            self.state.scroll_to(i);
+        }
+    }
+
+    pub fn visible_items(&self) -> &[T] {
+        &self.items[self.state.visible_range(self.items.len())]
     }
 
     pub fn is_empty(&self) -> bool {
         self.items.is_empty()
     }
 
+    /// Sorts everything and drops duplicate ids, only needed after changing `items` directly.
     pub fn update_order(&mut self) {
         self.items.sort_by(|a, b| b.cmp(a));
+        let mut ids = HashSet::new();
+        self.items.retain(|item| ids.insert(item.internal_id()));
         self.item_indices.clear();
         for (i, item) in self.items.iter().enumerate() {
             self.item_indices.insert(item.internal_id(), i);
@@ -111,7 +307,28 @@ impl<T> StatefulOrderedList<T>
         self.update_state();
     }
 
+    fn select_index(&mut self, i: usize) {
+        self.state.select(Some(i));
+        self.state.scroll_to(i);
+        self.selected_item_id = Some(self.items[i].internal_id());
+    }
+
+    fn reposition(&mut self, i: usize) {
+        let item = self.items.remove(i);
+        let position = self.items.partition_point(|x| x > &item);
+        self.items.insert(position, item);
+        self.reindex(i.min(position), i.max(position));
+        self.update_state();
+    }
+
+    fn reindex(&mut self, from: usize, to: usize) {
+        for i in from..=to {
+            self.item_indices.insert(self.items[i].internal_id(), i);
+        }
+    }
+
     fn update_state(&mut self) {
+        self.state.clamp(self.items.len());
         if self.selected_item_id.is_none() {
             self.state.select(None);
             return;
@@ -137,3 +354,212 @@ impl<T> Default for StatefulOrderedList<T>
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Item {
+        rank: u32,
+        id: &'static str,
+    }
+
+    impl InternalID for Item {
+        fn internal_id(&self) -> String {
+            self.id.to_string()
+        }
+    }
+
+    fn item(id: &'static str, rank: u32) -> Item {
+        Item { rank, id }
+    }
+
+    fn ids(list: &StatefulOrderedList<Item>) -> Vec<&'static str> {
+        list.items.iter().map(|i| i.id).collect()
+    }
+
+    fn assert_indexed(list: &StatefulOrderedList<Item>) {
+        assert_eq!(list.item_indices.len(), list.items.len());
+        for (i, item) in list.items.iter().enumerate() {
+            assert_eq!(list.position(item.id), Some(i));
+        }
+    }
+
+    #[test]
+    fn test_insert_keeps_order_and_upserts() {
+        let mut list = StatefulOrderedList::default();
+        assert_eq!(list.insert(item("a", 1)), None);
+        list.insert(item("b", 3));
+        list.insert(item("c", 2));
+        assert_eq!(ids(&list), vec!["b", "c", "a"]);
+
+        let old = list.upsert(item("a", 4));
+
+        assert_eq!(old, Some(item("a", 1)));
+        assert_eq!(ids(&list), vec!["a", "b", "c"]);
+        assert_indexed(&list);
+    }
+
+    #[test]
+    fn test_extend_deduplicates() {
+        let mut list = StatefulOrderedList::default();
+        list.push(item("a", 1));
+        list.extend(vec![item("b", 2), item("a", 5), item("b", 3)]);
+
+        assert_eq!(list.items, vec![item("a", 5), item("b", 3)]);
+        assert_indexed(&list);
+    }
+
+    #[test]
+    fn test_remove_keeps_selection() {
+        let mut list = StatefulOrderedList::default();
+        list.extend(vec![item("a", 3), item("b", 2), item("c", 1)]);
+        list.select("c");
+
+        assert_eq!(list.remove("a"), Some(item("a", 3)));
+        assert_eq!(list.remove("a"), None);
+
+        assert_eq!(ids(&list), vec!["b", "c"]);
+        assert_eq!(list.state.selected(), Some(1));
+        assert_indexed(&list);
+
+        list.remove("c");
+        assert_eq!(list.selected_item_id, None);
+        assert_eq!(list.state.selected(), None);
+    }
+
+    #[test]
+    fn test_update_with_repositions_item() {
+        let mut list = StatefulOrderedList::default();
+        let ids_by_rank = ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"];
+        list.extend(ids_by_rank.iter().zip(0..).map(|(id, rank)| item(id, rank)).collect());
+        list.select("e");
+
+        assert!(list.update_with("b", |i| i.rank = 100));
+        assert!(!list.update_with("x", |i| i.rank = 100));
+
+        assert_eq!(ids(&list), vec!["b", "j", "i", "h", "g", "f", "e", "d", "c", "a"]);
+        assert_eq!(list.state.selected(), Some(6));
+        assert_indexed(&list);
+
+        list.update_with("b", |i| i.rank = 0);
+        assert_eq!(ids(&list), vec!["j", "i", "h", "g", "f", "e", "d", "c", "b", "a"]);
+        assert_indexed(&list);
+    }
+
+    #[test]
+    fn test_moving_last_item_to_front_reindexes_everything() {
+        let mut list = numbered(10);
+        list.select("5");
+
+        assert!(list.update_with("0", |i| i.rank = 100));
+
+        assert_eq!(ids(&list), vec!["0", "9", "8", "7", "6", "5", "4", "3", "2", "1"]);
+        assert_eq!(list.position("0"), Some(0));
+        assert_eq!(list.position("1"), Some(9));
+        assert_eq!(list.state.selected(), Some(5));
+        assert_indexed(&list);
+
+        list.upsert(item("1", 200));
+        assert_eq!(ids(&list)[..2], ["1", "0"]);
+        assert_indexed(&list);
+    }
+
+    #[test]
+    fn test_update_with_rejects_duplicate_ids() {
+        let mut list = StatefulOrderedList::default();
+        list.extend(vec![item("a", 3), item("b", 2), item("c", 1)]);
+
+        assert!(!list.update_with("c", |i| *i = item("a", 10)));
+        assert_eq!(list.items, vec![item("a", 3), item("b", 2), item("c", 1)]);
+
+        assert!(list.update_with("c", |i| *i = item("d", 10)));
+        assert_eq!(ids(&list), vec!["d", "a", "b"]);
+        assert_eq!(list.find("c"), None);
+        assert_indexed(&list);
+    }
+
+    fn numbered(len: u32) -> StatefulOrderedList<Item> {
+        const IDS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+        let mut list = StatefulOrderedList::default();
+        list.extend((0..len).map(|rank| item(IDS[9 - rank as usize], 9 - rank)).collect());
+        list
+    }
+
+    #[test]
+    fn test_visible_range() {
+        let mut state = ListState::default();
+        assert_eq!(state.visible_range(5), 0..5);
+
+        state.set_height(3, 5);
+        state.set_offset(4, 5);
+
+        assert_eq!(state.offset(), 2);
+        assert_eq!(state.visible_range(5), 2..5);
+        assert_eq!(state.visible_range(1), 1..1);
+    }
+
+    #[test]
+    fn test_selection_stays_in_view() {
+        let mut list = numbered(10);
+        list.set_viewport_height(3);
+
+        for _ in 0..5 {
+            list.next();
+        }
+        assert_eq!(list.state.selected(), Some(4));
+        assert_eq!(list.state.visible_range(10), 2..5);
+
+        list.previous();
+        list.previous();
+        list.previous();
+        assert_eq!(list.state.visible_range(10), 1..4);
+
+        list.previous();
+        list.previous();
+        assert_eq!(list.state.selected(), Some(9));
+        assert_eq!(list.state.visible_range(10), 7..10);
+        assert_eq!(ids(&list)[7..], *list.visible_items().iter().map(|i| i.id).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_page_up_and_down() {
+        let mut list = numbered(10);
+        list.set_viewport_height(4);
+
+        list.page_down();
+        assert_eq!(list.state.selected(), Some(0));
+        assert_eq!(list.state.visible_range(10), 0..4);
+
+        list.page_down();
+        assert_eq!(list.state.selected(), Some(4));
+        assert_eq!(list.state.visible_range(10), 4..8);
+
+        list.page_down();
+        assert_eq!(list.state.selected(), Some(8));
+        assert_eq!(list.state.visible_range(10), 6..10);
+
+        list.page_down();
+        assert_eq!(list.state.selected(), Some(9));
+
+        list.page_up();
+        assert_eq!(list.state.selected(), Some(5));
+        assert_eq!(list.state.visible_range(10), 2..6);
+    }
+
+    #[test]
+    fn test_scroll_to_item_and_shrinking_list() {
+        let mut list = numbered(10);
+        list.set_viewport_height(3);
+
+        assert!(list.scroll_to_item("2"));
+        assert_eq!(list.state.visible_range(10), 5..8);
+        assert!(!list.scroll_to_item("x"));
+
+        for id in ["9", "8", "7", "6", "5"] {
+            list.remove(id);
+        }
+        assert_eq!(list.state.visible_range(list.len()), 2..5);
+    }
+}
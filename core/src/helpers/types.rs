@@ -1,3 +1,4 @@
 pub type ChatId = u32;
+pub type MessageId = String;
 pub type TextInput = String;
 pub type UserId = String;
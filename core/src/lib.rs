@@ -2,6 +2,8 @@ pub mod api;
 pub mod auth;
 pub mod config;
 pub mod helpers;
+pub mod media;
+pub mod messenger;
 pub mod prelude;
 pub mod storage;
 pub mod traits;
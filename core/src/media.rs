@@ -0,0 +1,245 @@
+//! Preparing files for upload.
+//!
+//! Photos straight from a phone are large and carry EXIF metadata, which often includes the
+//! location. Before they are uploaded, images are decoded, turned upright, downscaled to the
+//! server's limit, re-encoded without their metadata and get smaller variants for previews.
+
+use crate::api::schemas::{EncodedImage, FileUpload, ServerCapabilities};
+use crate::utils::format_file_size;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use std::fmt;
+use std::io::Cursor;
+
+const JPEG_QUALITY: u8 = 85;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaError {
+    TooLarge { size: u64, max: u64 },
+    InvalidImage(String),
+    UnsupportedImage(String),
+}
+
+impl fmt::Display for MediaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MediaError::TooLarge { size, max } => write!(
+                f,
+                "File is too large: {}, the limit is {}",
+                format_file_size(*size),
+                format_file_size(*max)
+            ),
+            MediaError::InvalidImage(e) => write!(f, "Invalid image: {}", e),
+            MediaError::UnsupportedImage(mime) => {
+                write!(f, "Images of type {} can't be sent", mime)
+            }
+        }
+    }
+}
+
+/// Processes images and checks the file against the server's limits.
+///
+/// Other files are only checked. Images in formats that aren't decoded here, e.g. HEIC, TIFF
+/// or GIF, are rejected, as their metadata can't be stripped.
+pub fn prepare_upload(
+    file: FileUpload,
+    capabilities: &ServerCapabilities,
+) -> Result<FileUpload, MediaError> {
+    let file = match decodable_format(&file.mime) {
+        Some(format) => prepare_image(file, format, capabilities)?,
+        None if file.mime.starts_with("image/") => {
+            return Err(MediaError::UnsupportedImage(file.mime));
+        }
+        None => file,
+    };
+    let size = file.data.len() as u64;
+    if size > capabilities.max_upload_size {
+        return Err(MediaError::TooLarge {
+            size,
+            max: capabilities.max_upload_size,
+        });
+    }
+    Ok(file)
+}
+
+fn decodable_format(mime: &str) -> Option<ImageFormat> {
+    match ImageFormat::from_mime_type(mime)? {
+        format @ (ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP) => Some(format),
+        _ => None,
+    }
+}
+
+fn prepare_image(
+    mut file: FileUpload,
+    format: ImageFormat,
+    capabilities: &ServerCapabilities,
+) -> Result<FileUpload, MediaError> {
+    let invalid = |e: image::ImageError| MediaError::InvalidImage(e.to_string());
+    let mut decoder = ImageReader::with_format(Cursor::new(&file.data), format)
+        .into_decoder()
+        .map_err(invalid)?;
+    let orientation = decoder.orientation().map_err(invalid)?;
+    let has_metadata = decoder.exif_metadata().map_err(invalid)?.is_some()
+        || decoder.xmp_metadata().map_err(invalid)?.is_some()
+        || decoder.iptc_metadata().map_err(invalid)?.is_some();
+    let mut image = DynamicImage::from_decoder(decoder).map_err(invalid)?;
+    image.apply_orientation(orientation);
+
+    let max = capabilities.max_image_dimension;
+    let too_large = image.width() > max || image.height() > max;
+    if too_large {
+        image = image.resize(max, max, FilterType::Lanczos3);
+    }
+    // re-encoding drops the metadata, the orientation has been applied to the pixels
+    if too_large || has_metadata {
+        let encoded = encode(&image, format)?;
+        if encoded.mime != file.mime {
+            file.name = rename_extension(&file.name, &encoded.mime);
+        }
+        file.mime = encoded.mime;
+        file.data = encoded.data;
+    }
+
+    let mut sizes = capabilities.image_variant_sizes.clone();
+    sizes.sort_unstable();
+    file.variants = sizes
+        .into_iter()
+        .filter(|size| *size < image.width().max(image.height()))
+        .map(|size| encode(&image.thumbnail(size, size), format))
+        .collect::<Result<_, _>>()?;
+    file.width = Some(image.width());
+    file.height = Some(image.height());
+    Ok(file)
+}
+
+/// Encodes in the original format where it's lossy or lossless anyway, WebP becomes JPEG,
+/// or PNG if it's transparent.
+fn encode(image: &DynamicImage, format: ImageFormat) -> Result<EncodedImage, MediaError> {
+    let png = match format {
+        ImageFormat::Png => true,
+        _ => image.color().has_alpha() && format != ImageFormat::Jpeg,
+    };
+    let mut data = vec![];
+    let result = if png {
+        image.write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+    } else {
+        let encoder = JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY);
+        image.to_rgb8().write_with_encoder(encoder)
+    };
+    result.map_err(|e| MediaError::InvalidImage(e.to_string()))?;
+
+    Ok(EncodedImage {
+        mime: if png { "image/png" } else { "image/jpeg" }.to_string(),
+        data,
+        width: image.width(),
+        height: image.height(),
+    })
+}
+
+fn rename_extension(name: &str, mime: &str) -> String {
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    let extension = if mime == "image/png" { "png" } else { "jpg" };
+    format!("{}.{}", stem, extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::ImageRgb8(RgbImage::new(width, height));
+        encode(&image, ImageFormat::Jpeg).unwrap().data
+    }
+
+    // an APP1 segment right after the start of image, with only the orientation in it
+    fn with_exif_orientation(jpeg: &[u8], orientation: u8) -> Vec<u8> {
+        let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0".to_vec();
+        exif.extend([orientation, 0, 0, 0, 0, 0, 0]);
+        let length = (exif.len() + 2) as u16;
+
+        let mut data = jpeg[..2].to_vec();
+        data.extend([0xFF, 0xE1]);
+        data.extend(length.to_be_bytes());
+        data.extend(exif);
+        data.extend(&jpeg[2..]);
+        data
+    }
+
+    fn capabilities() -> ServerCapabilities {
+        ServerCapabilities {
+            max_upload_size: 1024 * 1024,
+            max_image_dimension: 100,
+            image_variant_sizes: vec![50, 20, 400],
+        }
+    }
+
+    #[test]
+    fn test_large_image_is_downscaled_with_variants() {
+        let file = FileUpload::new("photo.jpg".into(), "image/jpeg".into(), jpeg(200, 100));
+
+        let file = prepare_upload(file, &capabilities()).unwrap();
+
+        assert_eq!((file.width, file.height), (Some(100), Some(50)));
+        let sizes: Vec<_> = file.variants.iter().map(|v| (v.width, v.height)).collect();
+        assert_eq!(sizes, [(20, 10), (50, 25)]);
+        let decoded = image::load_from_memory(&file.data).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (100, 50));
+    }
+
+    #[test]
+    fn test_exif_is_stripped_and_orientation_applied() {
+        // 6 means the camera was turned, the image has to be rotated clockwise
+        let data = with_exif_orientation(&jpeg(40, 20), 6);
+        assert!(data.windows(4).any(|w| w == b"Exif"));
+        let file = FileUpload::new("photo.jpg".into(), "image/jpeg".into(), data);
+
+        let file = prepare_upload(file, &capabilities()).unwrap();
+
+        assert!(!file.data.windows(4).any(|w| w == b"Exif"));
+        assert_eq!((file.width, file.height), (Some(20), Some(40)));
+    }
+
+    #[test]
+    fn test_small_image_without_metadata_is_kept() {
+        let data = jpeg(40, 20);
+        let file = FileUpload::new("photo.jpg".into(), "image/jpeg".into(), data.clone());
+
+        let file = prepare_upload(file, &capabilities()).unwrap();
+
+        assert_eq!(file.data, data);
+        assert_eq!((file.width, file.height), (Some(40), Some(20)));
+        assert_eq!(file.variants.len(), 1);
+    }
+
+    #[test]
+    fn test_images_that_cant_be_stripped_are_rejected() {
+        for mime in ["image/heic", "image/tiff", "image/gif"] {
+            let file = FileUpload::new("photo".into(), mime.into(), b"data".to_vec());
+
+            let error = prepare_upload(file, &capabilities()).unwrap_err();
+
+            assert_eq!(error, MediaError::UnsupportedImage(mime.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let capabilities = ServerCapabilities {
+            max_upload_size: 4,
+            ..capabilities()
+        };
+        let file = FileUpload::new("notes.txt".into(), "text/plain".into(), b"hello".to_vec());
+
+        let error = prepare_upload(file, &capabilities).unwrap_err();
+
+        assert_eq!(error, MediaError::TooLarge { size: 5, max: 4 });
+
+        let file = FileUpload::new("broken.png".into(), "image/png".into(), b"nope".to_vec());
+        assert!(matches!(
+            prepare_upload(file, &capabilities),
+            Err(MediaError::InvalidImage(_))
+        ));
+    }
+}
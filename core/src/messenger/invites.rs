@@ -0,0 +1,71 @@
+/// App route segment of invite links, they look like `.../apps/messenger/join/<code>`.
+pub const JOIN_SEGMENT: &str = "join";
+
+/// The invite code in what the user entered, either the code itself or a link with it.
+pub fn parse_invite_code(input: &str) -> Option<String> {
+    let input = input.trim();
+    let code = match input.split_once(&format!("/{}/", JOIN_SEGMENT)) {
+        Some((_, rest)) => rest.split(['/', '?', '#']).next().unwrap_or_default(),
+        None => input,
+    };
+    let valid = !code.is_empty()
+        && code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| code.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::schemas::ChatInvite;
+
+    #[test]
+    fn test_parse_invite_code() {
+        assert_eq!(parse_invite_code(" aB3-x_9 "), Some("aB3-x_9".to_string()));
+        assert_eq!(
+            parse_invite_code("https://link.example/apps/messenger/join/aB3x9?utm=1"),
+            Some("aB3x9".to_string())
+        );
+        assert_eq!(
+            parse_invite_code("/apps/messenger/join/aB3x9/"),
+            Some("aB3x9".to_string())
+        );
+        assert_eq!(
+            parse_invite_code("https://link.example/apps/messenger"),
+            None
+        );
+        assert_eq!(parse_invite_code(""), None);
+    }
+
+    #[test]
+    fn test_invite_expiry_and_uses() {
+        let invite = ChatInvite {
+            code: "abc".to_string(),
+            chat_id: 1,
+            created_by: "u1".to_string(),
+            created_at: 0.0,
+            expires_at: Some(100.0),
+            max_uses: Some(2),
+            uses: 1,
+        };
+        assert!(invite.is_active(50.0));
+        assert!(!invite.is_active(100.0));
+        assert!(
+            !ChatInvite {
+                uses: 2,
+                ..invite.clone()
+            }
+            .is_active(50.0)
+        );
+        assert!(
+            ChatInvite {
+                expires_at: None,
+                max_uses: None,
+                uses: 10,
+                ..invite
+            }
+            .is_active(1e12)
+        );
+    }
+}
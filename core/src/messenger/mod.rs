@@ -0,0 +1,5 @@
+pub mod invites;
+pub mod models;
+pub mod search;
+pub mod store;
+pub mod typing;
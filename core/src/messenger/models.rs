@@ -0,0 +1,231 @@
+use crate::api::schemas::{ChatFolder, ChatModel, ChatRole, ChatSettings, MessageModel};
+use crate::helpers::traits::InternalID;
+use crate::helpers::types::{ChatId, UserId};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Chat list entry, messages are kept separately by the store.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chat {
+    pub id: ChatId,
+    pub name: Option<String>,
+    pub member_ids: Vec<UserId>,
+    pub roles: HashMap<UserId, ChatRole>,
+    pub settings: ChatSettings,
+    pub last_message: Option<MessageModel>,
+    /// Unread messages from other members, kept up to date by the store.
+    pub unread_count: u32,
+}
+
+/// Which chats a tab of the chat list shows.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ChatFilter {
+    /// Everything but the archived chats.
+    #[default]
+    All,
+    Archived,
+    Folder(String),
+}
+
+impl ChatFilter {
+    /// Archived chats only show up in the archive, also when they are in a folder.
+    pub fn matches(&self, chat: &Chat, folders: &[ChatFolder]) -> bool {
+        match self {
+            ChatFilter::All => !chat.settings.archived,
+            ChatFilter::Archived => chat.settings.archived,
+            ChatFilter::Folder(id) => {
+                !chat.settings.archived
+                    && folders.iter().find(|f| &f.id == id).is_some_and(|folder| {
+                        folder.chat_ids.contains(&chat.id)
+                            || (folder.include_unread && chat.unread_count > 0)
+                    })
+            }
+        }
+    }
+}
+
+/// Changes a member can make to a group.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChatAction {
+    Rename,
+    AddMembers,
+    RemoveMember(UserId),
+    SetRole(UserId),
+    Leave,
+}
+
+impl Chat {
+    pub fn last_activity(&self) -> f64 {
+        self.last_message.as_ref().map_or(0.0, |m| m.created_at)
+    }
+
+    /// Direct chats are unnamed and between two users, they can't be changed.
+    pub fn is_group(&self) -> bool {
+        self.name.as_deref().is_some_and(|n| !n.is_empty()) || self.member_ids.len() > 2
+    }
+
+    /// The role of a member, `None` for everyone else.
+    pub fn role(&self, user_id: &UserId) -> Option<ChatRole> {
+        if !self.member_ids.contains(user_id) {
+            return None;
+        }
+        Some(self.roles.get(user_id).copied().unwrap_or_default())
+    }
+
+    /// The same checks the server makes, to only offer what it would accept.
+    ///
+    /// Admins rename the group and add members, and remove members below their own role.
+    /// Only the owner hands out and takes back the admin role.
+    pub fn allows(&self, user_id: &UserId, action: &ChatAction) -> bool {
+        let Some(role) = self.role(user_id).filter(|_| self.is_group()) else {
+            return false;
+        };
+        match action {
+            ChatAction::Rename | ChatAction::AddMembers => role >= ChatRole::Admin,
+            ChatAction::RemoveMember(member_id) => {
+                role >= ChatRole::Admin && self.role(member_id).is_some_and(|r| r < role)
+            }
+            ChatAction::SetRole(member_id) => {
+                role == ChatRole::Owner && member_id != user_id && self.role(member_id).is_some()
+            }
+            ChatAction::Leave => true,
+        }
+    }
+}
+
+impl From<&ChatModel> for Chat {
+    fn from(chat: &ChatModel) -> Self {
+        Self {
+            id: chat.id,
+            name: chat.name.clone(),
+            member_ids: chat.member_ids.clone(),
+            roles: chat.roles.clone(),
+            settings: chat.settings.clone(),
+            last_message: chat.messages.last().cloned(),
+            unread_count: 0,
+        }
+    }
+}
+
+impl InternalID for Chat {
+    fn internal_id(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+impl Eq for Chat {}
+
+impl PartialOrd for Chat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Greater is pinned or more recent, the list puts it first.
+impl Ord for Chat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.settings
+            .pinned
+            .cmp(&other.settings.pinned)
+            .then(self.last_activity().total_cmp(&other.last_activity()))
+            .then(self.id.cmp(&other.id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group() -> Chat {
+        Chat {
+            id: 1,
+            name: Some("Team".to_string()),
+            member_ids: vec!["owner".into(), "admin".into(), "a".into(), "b".into()],
+            roles: HashMap::from([
+                ("owner".to_string(), ChatRole::Owner),
+                ("admin".to_string(), ChatRole::Admin),
+            ]),
+            settings: ChatSettings::default(),
+            last_message: None,
+            unread_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_permissions_follow_roles() {
+        let chat = group();
+        let (owner, admin, member) = ("owner".to_string(), "admin".to_string(), "a".to_string());
+        let remove = |id: &str| ChatAction::RemoveMember(id.to_string());
+
+        assert!(chat.allows(&admin, &ChatAction::Rename));
+        assert!(!chat.allows(&member, &ChatAction::AddMembers));
+        assert!(chat.allows(&member, &ChatAction::Leave));
+        assert!(!chat.allows(&"stranger".to_string(), &ChatAction::Leave));
+
+        assert!(chat.allows(&admin, &remove("b")));
+        assert!(!chat.allows(&admin, &remove("owner")));
+        assert!(!chat.allows(&admin, &remove("admin")));
+        assert!(chat.allows(&owner, &remove("admin")));
+        assert!(!chat.allows(&member, &remove("b")));
+
+        assert!(chat.allows(&owner, &ChatAction::SetRole(member.clone())));
+        assert!(!chat.allows(&owner, &ChatAction::SetRole(owner.clone())));
+        assert!(!chat.allows(&admin, &ChatAction::SetRole(member)));
+    }
+
+    #[test]
+    fn test_folders_and_archive() {
+        let archived = Chat {
+            id: 2,
+            settings: ChatSettings {
+                archived: true,
+                ..Default::default()
+            },
+            ..group()
+        };
+        let unread = Chat {
+            id: 3,
+            unread_count: 1,
+            ..group()
+        };
+        let folders = [
+            ChatFolder {
+                id: "work".to_string(),
+                name: "Work".to_string(),
+                chat_ids: vec![1, 2],
+                include_unread: false,
+            },
+            ChatFolder {
+                id: "unread".to_string(),
+                name: "Unread".to_string(),
+                chat_ids: vec![],
+                include_unread: true,
+            },
+        ];
+        let shown = |filter: ChatFilter| {
+            [group(), archived.clone(), unread.clone()]
+                .iter()
+                .filter(|c| filter.matches(c, &folders))
+                .map(|c| c.id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(shown(ChatFilter::All), [1, 3]);
+        assert_eq!(shown(ChatFilter::Archived), [2]);
+        assert_eq!(shown(ChatFilter::Folder("work".to_string())), [1]);
+        assert_eq!(shown(ChatFilter::Folder("unread".to_string())), [3]);
+        assert!(shown(ChatFilter::Folder("gone".to_string())).is_empty());
+    }
+
+    #[test]
+    fn test_direct_chats_cant_be_changed() {
+        let chat = Chat {
+            name: None,
+            member_ids: vec!["owner".into(), "a".into()],
+            ..group()
+        };
+
+        assert!(!chat.allows(&"owner".to_string(), &ChatAction::Rename));
+        assert!(!chat.allows(&"a".to_string(), &ChatAction::Leave));
+    }
+}
@@ -0,0 +1,353 @@
+//! Full-text search over the messages the client has loaded.
+//!
+//! Text is split into words and folded, lowercase and without diacritics, so "Café" is found
+//! with "cafe". Every word of the query has to match the start of a word in the message,
+//! which also finds messages while the last word is still being typed.
+
+use crate::api::schemas::MessageModel;
+use crate::helpers::types::{ChatId, MessageId};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// Lowercase and without diacritics.
+pub fn fold(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// A word of a text, with its byte range in the original text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub term: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Splits the text into words, anything that isn't a letter or a digit separates them.
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut start = None;
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (start, c.is_alphanumeric()) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                tokens.push(Token {
+                    term: fold(&text[s..i]),
+                    start: s,
+                    end: i,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+#[derive(Debug, Clone)]
+struct IndexedMessage {
+    chat_id: ChatId,
+    created_at: f64,
+    terms: HashSet<String>,
+}
+
+/// Inverted index from folded words to the messages containing them.
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    // sorted, so the words starting with a prefix are a range
+    terms: BTreeMap<String, HashSet<MessageId>>,
+    messages: HashMap<MessageId, IndexedMessage>,
+}
+
+impl SearchIndex {
+    /// Adds the message, or re-indexes it after an edit. Deleted and system messages are
+    /// removed instead.
+    pub fn index(&mut self, message: &MessageModel) {
+        self.remove(&message.id);
+        if message.deleted || message.system.is_some() {
+            return;
+        }
+        let terms: HashSet<String> = tokenize(&message.text)
+            .into_iter()
+            .map(|t| t.term)
+            .chain(
+                message
+                    .attachments
+                    .iter()
+                    .flat_map(|a| tokenize(&a.name))
+                    .map(|t| t.term),
+            )
+            .collect();
+        if terms.is_empty() {
+            return;
+        }
+        for term in &terms {
+            self.terms
+                .entry(term.clone())
+                .or_default()
+                .insert(message.id.clone());
+        }
+        self.messages.insert(
+            message.id.clone(),
+            IndexedMessage {
+                chat_id: message.chat_id,
+                created_at: message.created_at,
+                terms,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, message_id: &MessageId) {
+        let Some(message) = self.messages.remove(message_id) else {
+            return;
+        };
+        for term in message.terms {
+            if let Some(ids) = self.terms.get_mut(&term) {
+                ids.remove(message_id);
+                if ids.is_empty() {
+                    self.terms.remove(&term);
+                }
+            }
+        }
+    }
+
+    pub fn remove_chat(&mut self, chat_id: ChatId) {
+        let ids: Vec<MessageId> = self
+            .messages
+            .iter()
+            .filter(|(_, m)| m.chat_id == chat_id)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in ids {
+            self.remove(&id);
+        }
+    }
+
+    /// Messages matching every word of the query, most recent first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<(ChatId, MessageId)> {
+        let mut matches: Option<HashSet<&MessageId>> = None;
+        for token in tokenize(query) {
+            let ids: HashSet<&MessageId> = self
+                .terms
+                .range(token.term.clone()..)
+                .take_while(|(term, _)| term.starts_with(&token.term))
+                .flat_map(|(_, ids)| ids)
+                .collect();
+            matches = Some(match matches {
+                Some(found) => found.intersection(&ids).copied().collect(),
+                None => ids,
+            });
+        }
+
+        let mut hits: Vec<(&MessageId, &IndexedMessage)> = matches
+            .unwrap_or_default()
+            .into_iter()
+            .map(|id| (id, &self.messages[id]))
+            .collect();
+        hits.sort_by(|a, b| b.1.created_at.total_cmp(&a.1.created_at));
+        hits.into_iter()
+            .take(limit)
+            .map(|(id, m)| (m.chat_id, id.clone()))
+            .collect()
+    }
+}
+
+/// Part of a snippet, highlighted where it matches the query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnippetPart {
+    pub text: String,
+    pub highlighted: bool,
+}
+
+/// The text around the first match, about `max_chars` long, with the matching word starts
+/// highlighted.
+pub fn snippet(text: &str, query: &str, max_chars: usize) -> Vec<SnippetPart> {
+    let prefixes: Vec<String> = tokenize(query).into_iter().map(|t| t.term).collect();
+    // highlights the length of the prefix, in the original text
+    let highlights: Vec<(usize, usize)> = tokenize(text)
+        .into_iter()
+        .filter_map(|token| {
+            let prefix = prefixes
+                .iter()
+                .filter(|p| token.term.starts_with(p.as_str()))
+                .max_by_key(|p| p.len())?;
+            let word = &text[token.start..token.end];
+            let end = word
+                .char_indices()
+                .map(|(i, c)| i + c.len_utf8())
+                .find(|i| fold(&word[..*i]).len() >= prefix.len())
+                .unwrap_or(word.len());
+            Some((token.start, token.start + end))
+        })
+        .collect();
+
+    // starts a few words before the first match when the text is too long
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let first = highlights.first().map_or(0, |(start, _)| *start);
+    let first_char = chars.iter().position(|(i, _)| *i >= first).unwrap_or(0);
+    let mut from = first_char.saturating_sub(max_chars / 4);
+    if chars.len() - from < max_chars {
+        from = chars.len().saturating_sub(max_chars);
+    }
+    let to = (from + max_chars).min(chars.len());
+    let byte = |i: usize| chars.get(i).map_or(text.len(), |(b, _)| *b);
+    let (start, end) = (byte(from), byte(to));
+
+    let mut parts = vec![];
+    let mut push = |text: &str, highlighted: bool| {
+        if !text.is_empty() {
+            parts.push(SnippetPart {
+                text: text.to_string(),
+                highlighted,
+            });
+        }
+    };
+    if start > 0 {
+        push("…", false);
+    }
+    let mut position = start;
+    for (h_start, h_end) in highlights {
+        let (h_start, h_end) = (h_start.max(position), h_end.min(end));
+        if h_start >= h_end {
+            continue;
+        }
+        push(&text[position..h_start], false);
+        push(&text[h_start..h_end], true);
+        position = h_end;
+    }
+    push(&text[position..end], false);
+    if end < text.len() {
+        push("…", false);
+    }
+    parts
+}
+
+/// Groups the messages by chat, the chat of the first message comes first.
+pub fn group_by_chat(messages: Vec<MessageModel>) -> Vec<(ChatId, Vec<MessageModel>)> {
+    let mut groups: Vec<(ChatId, Vec<MessageModel>)> = vec![];
+    for message in messages {
+        match groups.iter_mut().find(|(id, _)| *id == message.chat_id) {
+            Some((_, messages)) => messages.push(message),
+            None => groups.push((message.chat_id, vec![message])),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::schemas::{DeliveryStatus, SystemEvent};
+
+    fn message(id: &str, chat_id: ChatId, text: &str, created_at: f64) -> MessageModel {
+        MessageModel {
+            id: id.to_string(),
+            seq: created_at as u64,
+            client_id: None,
+            chat_id,
+            sender_id: "u1".to_string(),
+            text: text.to_string(),
+            created_at,
+            is_read: false,
+            status: DeliveryStatus::Sent,
+            edited_at: None,
+            edits: vec![],
+            deleted: false,
+            reply_to: None,
+            reactions: vec![],
+            attachments: vec![],
+            system: None,
+        }
+    }
+
+    fn ids(hits: Vec<(ChatId, MessageId)>) -> Vec<MessageId> {
+        hits.into_iter().map(|(_, id)| id).collect()
+    }
+
+    #[test]
+    fn test_tokenize_and_fold() {
+        let tokens = tokenize("Crème brûlée, s'il-vous-plaît!");
+        let terms: Vec<&str> = tokens.iter().map(|t| t.term.as_str()).collect();
+
+        assert_eq!(terms, ["creme", "brulee", "s", "il", "vous", "plait"]);
+        assert_eq!(&"Crème brûlée"[tokens[1].start..tokens[1].end], "brûlée");
+    }
+
+    #[test]
+    fn test_search_matches_prefixes_of_every_word() {
+        let mut index = SearchIndex::default();
+        index.index(&message("a", 1, "Meeting at the Café tomorrow", 10.0));
+        index.index(&message("b", 2, "cafeteria is closed", 20.0));
+        index.index(&message("c", 1, "see you tomorrow", 30.0));
+
+        assert_eq!(ids(index.search("café", 10)), ["b", "a"]);
+        assert_eq!(ids(index.search("CAFE tom", 10)), ["a"]);
+        assert_eq!(ids(index.search("tomorrow", 1)), ["c"]);
+        assert!(index.search("caff", 10).is_empty());
+        assert!(index.search("  ", 10).is_empty());
+    }
+
+    #[test]
+    fn test_edits_and_deletions_update_the_index() {
+        let mut index = SearchIndex::default();
+        let original = message("a", 1, "old text", 10.0);
+        index.index(&original);
+
+        index.index(&MessageModel {
+            text: "new text".to_string(),
+            ..original.clone()
+        });
+        assert!(index.search("old", 10).is_empty());
+        assert_eq!(ids(index.search("new", 10)), ["a"]);
+
+        index.index(&MessageModel {
+            deleted: true,
+            ..original.clone()
+        });
+        assert!(index.search("text", 10).is_empty());
+
+        index.index(&MessageModel {
+            system: Some(SystemEvent::MemberLeft),
+            ..original.clone()
+        });
+        index.index(&message("b", 2, "text", 20.0));
+        index.remove_chat(2);
+        assert!(index.search("text", 10).is_empty());
+        assert!(index.terms.is_empty());
+    }
+
+    #[test]
+    fn test_snippet_highlights_matching_word_starts() {
+        let parts = snippet("Meet at the Café", "caf", 100);
+        let rendered: Vec<(&str, bool)> = parts
+            .iter()
+            .map(|p| (p.text.as_str(), p.highlighted))
+            .collect();
+
+        assert_eq!(
+            rendered,
+            [("Meet at the ", false), ("Caf", true), ("é", false)]
+        );
+
+        let long = format!("{} needle {}", "word ".repeat(50), "word ".repeat(50));
+        let parts = snippet(&long, "needle", 40);
+        assert_eq!(parts.first().unwrap().text, "…");
+        assert_eq!(parts.last().unwrap().text, "…");
+        assert!(parts.iter().any(|p| p.highlighted && p.text == "needle"));
+    }
+
+    #[test]
+    fn test_group_by_chat() {
+        let groups = group_by_chat(vec![
+            message("a", 2, "x", 30.0),
+            message("b", 1, "x", 20.0),
+            message("c", 2, "x", 10.0),
+        ]);
+        let groups: Vec<(ChatId, usize)> = groups.iter().map(|(id, m)| (*id, m.len())).collect();
+
+        assert_eq!(groups, [(2, 2), (1, 1)]);
+    }
+}
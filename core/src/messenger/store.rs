@@ -0,0 +1,1540 @@
+use crate::api::schemas::{
+    ChatFolder, ChatModel, ChatRole, ChatSettings, DeliveryReceipt, DeliveryStatus, MessageModel,
+    MessagePage, Presence, Reaction, ReactionUpdate, ReadWatermark, SystemEvent, TypingUpdate,
+    User, WsEvent,
+};
+use crate::helpers::list::StatefulOrderedList;
+use crate::helpers::types::{ChatId, MessageId, UserId};
+use crate::messenger::models::{Chat, ChatFilter};
+use crate::messenger::search::SearchIndex;
+use crate::messenger::typing::TYPING_TIMEOUT;
+use crate::utils::now_timestamp;
+use std::collections::{HashMap, HashSet};
+
+/// What an update has changed, so views can refresh only the affected parts.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Changes {
+    pub chats: HashSet<ChatId>,
+    pub messages: HashSet<ChatId>,
+    pub users: bool,
+    pub order: bool,
+    pub presence: bool,
+    pub typing: HashSet<ChatId>,
+    pub read: HashSet<ChatId>,
+    pub folders: bool,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.chats.is_empty()
+            && self.messages.is_empty()
+            && !self.users
+            && !self.order
+            && !self.presence
+            && self.typing.is_empty()
+            && self.read.is_empty()
+            && !self.folders
+    }
+
+    pub fn merge(&mut self, other: Changes) {
+        self.chats.extend(other.chats);
+        self.messages.extend(other.messages);
+        self.users |= other.users;
+        self.order |= other.order;
+        self.presence |= other.presence;
+        self.typing.extend(other.typing);
+        self.read.extend(other.read);
+        self.folders |= other.folders;
+    }
+}
+
+/// Client-side state of the messenger.
+///
+/// Every `apply_*` is idempotent, so the same data can arrive from the API and the socket
+/// in any order.
+#[derive(Default)]
+pub struct MessengerStore {
+    pub me: Option<UserId>,
+    chats: StatefulOrderedList<Chat>,
+    messages: HashMap<ChatId, Vec<MessageModel>>,
+    users: HashMap<UserId, User>,
+    // deleted for the current user, kept so that late copies don't bring them back
+    hidden: HashSet<MessageId>,
+    // chats whose oldest message is loaded
+    complete_history: HashSet<ChatId>,
+    presence: HashMap<UserId, Presence>,
+    // who is typing in a chat and until when, in the order they started
+    typing: HashMap<ChatId, Vec<(UserId, f64)>>,
+    // seq of the last message each member has read, only ever moves forward
+    read_watermarks: HashMap<ChatId, HashMap<UserId, u64>>,
+    folders: Vec<ChatFolder>,
+    search: SearchIndex,
+}
+
+impl MessengerStore {
+    pub fn chat(&self, chat_id: ChatId) -> Option<&Chat> {
+        self.chats.find(&chat_id.to_string())
+    }
+
+    /// Chat ids, most recent first.
+    pub fn chat_ids(&self) -> Vec<ChatId> {
+        self.chats.items.iter().map(|c| c.id).collect()
+    }
+
+    pub fn messages(&self, chat_id: ChatId) -> &[MessageModel] {
+        self.messages.get(&chat_id).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn message(&self, chat_id: ChatId, message_id: &MessageId) -> Option<&MessageModel> {
+        self.messages(chat_id).iter().find(|m| &m.id == message_id)
+    }
+
+    /// Seq of the oldest loaded message, the next page of history goes before it.
+    pub fn oldest_seq(&self, chat_id: ChatId) -> Option<u64> {
+        self.messages(chat_id).first().map(|m| m.seq)
+    }
+
+    pub fn has_older_messages(&self, chat_id: ChatId) -> bool {
+        !self.complete_history.contains(&chat_id)
+    }
+
+    pub fn user(&self, user_id: &UserId) -> Option<&User> {
+        self.users.get(user_id)
+    }
+
+    pub fn users(&self) -> &HashMap<UserId, User> {
+        &self.users
+    }
+
+    /// Members of the known chats that have to be fetched.
+    pub fn missing_user_ids(&self) -> Vec<UserId> {
+        let mut missing: Vec<UserId> = self
+            .chats
+            .items
+            .iter()
+            .flat_map(|c| c.member_ids.iter())
+            .filter(|id| !self.users.contains_key(*id))
+            .cloned()
+            .collect();
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+
+    pub fn selected_chat_id(&self) -> Option<ChatId> {
+        self.chats.state.selected().map(|i| self.chats.items[i].id)
+    }
+
+    /// Keeps the list selection in line with the opened chat.
+    pub fn select_chat(&mut self, chat_id: Option<ChatId>) {
+        match chat_id.filter(|id| self.chat(*id).is_some()) {
+            Some(chat_id) => self.chats.select(&chat_id.to_string()),
+            None => self.chats.unselect(),
+        }
+    }
+
+    /// Selects the chat below the selected one, wrapping around.
+    pub fn select_next(&mut self) -> Option<ChatId> {
+        self.chats.next();
+        self.selected_chat_id()
+    }
+
+    pub fn select_previous(&mut self) -> Option<ChatId> {
+        self.chats.previous();
+        self.selected_chat_id()
+    }
+
+    /// The unnamed chat of the current user with only the given user.
+    pub fn direct_chat_with(&self, user_id: &UserId) -> Option<ChatId> {
+        let mut members = vec![user_id];
+        if let Some(me) = self.me.as_ref()
+            && me != user_id
+        {
+            members.push(me);
+        }
+        members.sort();
+
+        self.chats
+            .items
+            .iter()
+            .filter(|c| c.name.as_deref().is_none_or(str::is_empty))
+            .find(|c| {
+                let mut ids: Vec<&UserId> = c.member_ids.iter().collect();
+                ids.sort();
+                ids.dedup();
+                ids == members
+            })
+            .map(|c| c.id)
+    }
+
+    /// Group chats use their name, direct chats the usernames of the other members.
+    pub fn chat_title(&self, chat: &Chat) -> String {
+        if let Some(name) = chat.name.as_ref().filter(|n| !n.is_empty()) {
+            return name.clone();
+        }
+        let names: Vec<&str> = chat
+            .member_ids
+            .iter()
+            .filter(|id| Some(*id) != self.me.as_ref())
+            .map(|id| {
+                self.users
+                    .get(id)
+                    .map_or("Unknown", |u| u.username.as_str())
+            })
+            .collect();
+        if names.is_empty() {
+            "Saved messages".to_string()
+        } else {
+            names.join(", ")
+        }
+    }
+
+    /// What a system message says, e.g. "jane added bob".
+    pub fn system_text(&self, message: &MessageModel) -> Option<String> {
+        let name = |id: &UserId| {
+            self.users
+                .get(id)
+                .map_or("Unknown", |u| u.username.as_str())
+        };
+        let actor = name(&message.sender_id);
+        let text = match message.system.as_ref()? {
+            SystemEvent::Created => format!("{} created the group", actor),
+            SystemEvent::Renamed { name: Some(title) } => {
+                format!("{} renamed the group to \"{}\"", actor, title)
+            }
+            SystemEvent::Renamed { name: None } => format!("{} removed the group name", actor),
+            SystemEvent::MembersAdded { user_ids } => {
+                let names: Vec<&str> = user_ids.iter().map(name).collect();
+                format!("{} added {}", actor, names.join(", "))
+            }
+            SystemEvent::MemberRemoved { user_id } => {
+                format!("{} removed {}", actor, name(user_id))
+            }
+            SystemEvent::MemberLeft => format!("{} left", actor),
+            SystemEvent::RoleChanged { user_id, role } => match role {
+                ChatRole::Owner => format!("{} made {} the owner", actor, name(user_id)),
+                ChatRole::Admin => format!("{} made {} an admin", actor, name(user_id)),
+                ChatRole::Member => format!("{} removed {} as admin", actor, name(user_id)),
+            },
+        };
+        Some(text)
+    }
+
+    pub fn apply_chats(&mut self, chats: Vec<ChatModel>) -> Changes {
+        let mut changes = Changes::default();
+        for chat in chats {
+            changes.merge(self.apply_chat(chat));
+        }
+        changes
+    }
+
+    pub fn apply_chat(&mut self, chat: ChatModel) -> Changes {
+        let mut changes = Changes::default();
+        let chat_id = chat.id;
+        // removed from the chat, or left it on another device
+        if let Some(me) = self.me.as_ref()
+            && !chat.member_ids.contains(me)
+        {
+            return self.remove_chat(chat_id);
+        }
+        let messages = chat.messages.clone();
+        let read_watermarks = chat.read_watermarks.clone();
+
+        let mut entry = Chat::from(&chat);
+        entry.last_message = None;
+        match self.chat(chat_id).cloned() {
+            Some(existing) => {
+                entry.last_message = existing.last_message.clone();
+                entry.unread_count = existing.unread_count;
+                if existing != entry {
+                    changes.order |= moves_in_list(&existing.settings, &entry.settings);
+                    self.replace_chat(entry);
+                    changes.chats.insert(chat_id);
+                }
+            }
+            None => {
+                self.chats.insert(entry);
+                changes.chats.insert(chat_id);
+                changes.order = true;
+            }
+        }
+
+        for message in messages {
+            changes.merge(self.apply_message(message));
+        }
+        for (user_id, seq) in read_watermarks {
+            changes.merge(self.apply_read_watermark(ReadWatermark {
+                chat_id,
+                user_id,
+                seq,
+            }));
+        }
+        changes
+    }
+
+    /// Forgets a chat the current user is no longer a member of.
+    pub fn remove_chat(&mut self, chat_id: ChatId) -> Changes {
+        let mut changes = Changes::default();
+        if self.chats.remove(&chat_id.to_string()).is_none() {
+            return changes;
+        }
+        self.messages.remove(&chat_id);
+        self.complete_history.remove(&chat_id);
+        self.typing.remove(&chat_id);
+        self.read_watermarks.remove(&chat_id);
+        self.search.remove_chat(chat_id);
+        changes.chats.insert(chat_id);
+        changes.messages.insert(chat_id);
+        changes.order = true;
+        changes
+    }
+
+    pub fn apply_message(&mut self, mut message: MessageModel) -> Changes {
+        let mut changes = Changes::default();
+        let chat_id = message.chat_id;
+
+        if self.hidden.contains(&message.id) {
+            return changes;
+        }
+        let messages = self.messages.entry(chat_id).or_default();
+        if let Some(i) = messages.iter().position(|m| is_same_message(m, &message)) {
+            if is_older_version(&message, &messages[i]) {
+                return changes;
+            }
+            // e.g. it has been read since, or it's the server's copy of a pending message
+            message.status = messages[i].status.merge(message.status);
+            if messages[i] == message {
+                return changes;
+            }
+            messages.remove(i);
+        }
+        self.search.index(&message);
+        let sender_id = message.sender_id.clone();
+        let position = messages.partition_point(|m| m.seq <= message.seq);
+        let is_latest = position == messages.len();
+        messages.insert(position, message);
+
+        // the message they were typing has arrived, unlike one from the history
+        if is_latest {
+            self.stop_typing(chat_id, &sender_id, &mut changes);
+        }
+        changes.messages.insert(chat_id);
+        self.refresh_chat(chat_id, &mut changes);
+        changes
+    }
+
+    /// Adds a page of older messages.
+    pub fn apply_history(&mut self, chat_id: ChatId, page: MessagePage) -> Changes {
+        let mut changes = Changes::default();
+        for message in page.messages {
+            changes.merge(self.apply_message(message));
+        }
+        if !page.has_more && self.complete_history.insert(chat_id) {
+            changes.messages.insert(chat_id);
+        }
+        changes
+    }
+
+    /// Removes a message deleted for the current user only.
+    pub fn hide_message(&mut self, chat_id: ChatId, message_id: &MessageId) -> Changes {
+        let mut changes = Changes::default();
+        self.hidden.insert(message_id.clone());
+        self.search.remove(message_id);
+        let Some(messages) = self.messages.get_mut(&chat_id) else {
+            return changes;
+        };
+        let count = messages.len();
+        messages.retain(|m| &m.id != message_id);
+        if messages.len() != count {
+            changes.messages.insert(chat_id);
+            self.refresh_chat(chat_id, &mut changes);
+        }
+        changes
+    }
+
+    /// Leaves the tombstone of a message deleted for everyone, the same the server sends for it.
+    pub fn delete_message(&mut self, chat_id: ChatId, message_id: &MessageId) -> Changes {
+        let Some(message) = self.message(chat_id, message_id) else {
+            return Changes::default();
+        };
+        let tombstone = MessageModel {
+            text: String::new(),
+            edits: vec![],
+            deleted: true,
+            reply_to: None,
+            reactions: vec![],
+            attachments: vec![],
+            ..message.clone()
+        };
+        self.apply_message(tombstone)
+    }
+
+    /// Marks a pending message as failed, it keeps its place until it's sent again.
+    pub fn mark_failed(&mut self, chat_id: ChatId, client_id: &str) -> Changes {
+        let mut changes = Changes::default();
+        let Some(message) = self.messages.get_mut(&chat_id).and_then(|messages| {
+            messages
+                .iter_mut()
+                .find(|m| m.client_id.as_deref() == Some(client_id))
+        }) else {
+            return changes;
+        };
+        if message.status == DeliveryStatus::Pending {
+            message.status = DeliveryStatus::Failed;
+            changes.messages.insert(chat_id);
+            self.refresh_chat(chat_id, &mut changes);
+        }
+        changes
+    }
+
+    pub fn apply_receipt(&mut self, receipt: DeliveryReceipt) -> Changes {
+        let mut changes = Changes::default();
+        let me = self.me.clone();
+        let Some(messages) = self.messages.get_mut(&receipt.chat_id) else {
+            return changes;
+        };
+        for message in messages.iter_mut().filter(|m| {
+            m.seq <= receipt.seq
+                && Some(&m.sender_id) == me.as_ref()
+                && !matches!(m.status, DeliveryStatus::Pending | DeliveryStatus::Failed)
+        }) {
+            let status = message.status.merge(receipt.status);
+            if status != message.status {
+                message.status = status;
+                changes.messages.insert(receipt.chat_id);
+            }
+        }
+        self.refresh_chat(receipt.chat_id, &mut changes);
+        changes
+    }
+
+    /// Adds or removes a user's reaction, applying the same update twice changes nothing.
+    pub fn apply_reaction(&mut self, update: ReactionUpdate) -> Changes {
+        let mut changes = Changes::default();
+        let Some(message) = self
+            .messages
+            .get_mut(&update.chat_id)
+            .and_then(|messages| messages.iter_mut().find(|m| m.id == update.message_id))
+        else {
+            return changes;
+        };
+        let reactions = &mut message.reactions;
+        let position = reactions.iter().position(|r| r.emoji == update.emoji);
+        let reacted = position.is_some_and(|i| reactions[i].user_ids.contains(&update.user_id));
+        match (position, update.added) {
+            (_, true) if reacted => return changes,
+            (Some(i), true) => {
+                reactions[i].count += 1;
+                reactions[i].user_ids.push(update.user_id);
+            }
+            (None, true) => reactions.push(Reaction {
+                emoji: update.emoji,
+                count: 1,
+                user_ids: vec![update.user_id],
+            }),
+            (Some(i), false) if reacted => {
+                reactions[i].count = reactions[i].count.saturating_sub(1);
+                reactions[i].user_ids.retain(|id| id != &update.user_id);
+                if reactions[i].count == 0 {
+                    reactions.remove(i);
+                }
+            }
+            (_, false) => return changes,
+        }
+        changes.messages.insert(update.chat_id);
+        self.refresh_chat(update.chat_id, &mut changes);
+        changes
+    }
+
+    /// Marks the messages of the other members as read, e.g. after the server has been told.
+    pub fn mark_read(&mut self, chat_id: ChatId) -> Changes {
+        let mut changes = Changes::default();
+        let me = self.me.clone();
+        let Some(messages) = self.messages.get_mut(&chat_id) else {
+            return changes;
+        };
+        for message in messages
+            .iter_mut()
+            .filter(|m| !m.is_read && Some(&m.sender_id) != me.as_ref())
+        {
+            message.is_read = true;
+            changes.messages.insert(chat_id);
+        }
+        // messages the server hasn't acknowledged don't have a seq yet
+        let last_seq = self
+            .messages(chat_id)
+            .iter()
+            .rev()
+            .find(|m| !matches!(m.status, DeliveryStatus::Pending | DeliveryStatus::Failed))
+            .map(|m| m.seq);
+        if let Some(me) = me
+            && let Some(seq) = last_seq
+        {
+            self.advance_read_watermark(chat_id, me, seq, &mut changes);
+        }
+        self.refresh_chat(chat_id, &mut changes);
+        changes
+    }
+
+    /// Seq of the last message each member has read.
+    pub fn read_watermarks(&self, chat_id: ChatId) -> HashMap<UserId, u64> {
+        self.read_watermarks
+            .get(&chat_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Other members who have read the message, ordered by id.
+    pub fn readers(&self, message: &MessageModel) -> Vec<UserId> {
+        let Some(watermarks) = self.read_watermarks.get(&message.chat_id) else {
+            return vec![];
+        };
+        let mut readers: Vec<UserId> = watermarks
+            .iter()
+            .filter(|(id, seq)| {
+                **seq >= message.seq && **id != message.sender_id && Some(*id) != self.me.as_ref()
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        readers.sort();
+        readers
+    }
+
+    /// A watermark behind the known one is ignored, they can arrive out of order.
+    ///
+    /// The current user's own watermark, e.g. from another device, marks the messages up to
+    /// it as read.
+    pub fn apply_read_watermark(&mut self, watermark: ReadWatermark) -> Changes {
+        let mut changes = Changes::default();
+        let chat_id = watermark.chat_id;
+        let is_me = Some(&watermark.user_id) == self.me.as_ref();
+        if self.advance_read_watermark(chat_id, watermark.user_id, watermark.seq, &mut changes)
+            && is_me
+        {
+            self.refresh_chat(chat_id, &mut changes);
+        }
+        changes
+    }
+
+    /// Index of the first unread message from another member.
+    pub fn first_unread(&self, chat_id: ChatId) -> Option<usize> {
+        self.messages(chat_id)
+            .iter()
+            .position(|m| self.is_unread(m))
+    }
+
+    /// Loaded messages matching the query, most recent first, see [`SearchIndex`].
+    pub fn search_messages(&self, query: &str, limit: usize) -> Vec<&MessageModel> {
+        self.search
+            .search(query, limit)
+            .into_iter()
+            .filter_map(|(chat_id, id)| self.message(chat_id, &id))
+            .collect()
+    }
+
+    /// Unread messages in the chats that aren't muted.
+    pub fn total_unread(&self) -> u32 {
+        let now = now_timestamp();
+        self.chats
+            .items
+            .iter()
+            .filter(|c| !c.settings.is_muted(now))
+            .map(|c| c.unread_count)
+            .sum()
+    }
+
+    /// Chat ids in the tab, pinned ones first, then most recent first.
+    pub fn filtered_chat_ids(&self, filter: &ChatFilter) -> Vec<ChatId> {
+        self.chats
+            .items
+            .iter()
+            .filter(|c| filter.matches(c, &self.folders))
+            .map(|c| c.id)
+            .collect()
+    }
+
+    pub fn apply_chat_settings(&mut self, chat_id: ChatId, settings: ChatSettings) -> Changes {
+        let mut changes = Changes::default();
+        let Some(mut chat) = self.chat(chat_id).cloned() else {
+            return changes;
+        };
+        if chat.settings == settings {
+            return changes;
+        }
+        changes.order = moves_in_list(&chat.settings, &settings);
+        chat.settings = settings;
+        self.replace_chat(chat);
+        changes.chats.insert(chat_id);
+        changes
+    }
+
+    pub fn folders(&self) -> &[ChatFolder] {
+        &self.folders
+    }
+
+    /// Replaces all folders, e.g. when they are loaded.
+    pub fn apply_folders(&mut self, folders: Vec<ChatFolder>) -> Changes {
+        let mut changes = Changes::default();
+        if self.folders != folders {
+            self.folders = folders;
+            changes.folders = true;
+        }
+        changes
+    }
+
+    /// Adds the folder or replaces the one with the same id.
+    pub fn apply_folder(&mut self, folder: ChatFolder) -> Changes {
+        let mut changes = Changes::default();
+        match self.folders.iter_mut().find(|f| f.id == folder.id) {
+            Some(current) if *current == folder => return changes,
+            Some(current) => *current = folder,
+            None => self.folders.push(folder),
+        }
+        changes.folders = true;
+        changes
+    }
+
+    pub fn remove_folder(&mut self, folder_id: &str) -> Changes {
+        let mut changes = Changes::default();
+        let count = self.folders.len();
+        self.folders.retain(|f| f.id != folder_id);
+        changes.folders = self.folders.len() != count;
+        changes
+    }
+
+    pub fn apply_users(&mut self, users: Vec<User>) -> Changes {
+        let mut changes = Changes::default();
+        for user in users {
+            if self.users.get(&user.id) != Some(&user) {
+                self.users.insert(user.id.clone(), user);
+                changes.users = true;
+            }
+        }
+        changes
+    }
+
+    pub fn presence(&self, user_id: &UserId) -> Option<&Presence> {
+        self.presence.get(user_id)
+    }
+
+    pub fn presences(&self) -> &HashMap<UserId, Presence> {
+        &self.presence
+    }
+
+    pub fn apply_presence(&mut self, mut presence: Presence) -> Changes {
+        let mut changes = Changes::default();
+        if let Some(current) = self.presence.get(&presence.user_id) {
+            presence.last_seen = presence.last_seen.or(current.last_seen);
+            if current == &presence {
+                return changes;
+            }
+        }
+        self.presence.insert(presence.user_id.clone(), presence);
+        changes.presence = true;
+        changes
+    }
+
+    /// Other members typing in the chat, in the order they started.
+    pub fn typing_users(&self, chat_id: ChatId) -> Vec<UserId> {
+        self.typing.get(&chat_id).map_or(vec![], |typing| {
+            typing.iter().map(|(id, _)| id.clone()).collect()
+        })
+    }
+
+    /// A typing event holds until `now + TYPING_TIMEOUT`, unless it's repeated.
+    pub fn apply_typing(&mut self, update: TypingUpdate, now: f64) -> Changes {
+        let mut changes = Changes::default();
+        if Some(&update.user_id) == self.me.as_ref() {
+            return changes;
+        }
+        if !update.typing {
+            self.stop_typing(update.chat_id, &update.user_id, &mut changes);
+            return changes;
+        }
+        let typing = self.typing.entry(update.chat_id).or_default();
+        let until = now + TYPING_TIMEOUT;
+        match typing.iter_mut().find(|(id, _)| id == &update.user_id) {
+            Some((_, expires_at)) => *expires_at = until,
+            None => {
+                typing.push((update.user_id, until));
+                changes.typing.insert(update.chat_id);
+            }
+        }
+        changes
+    }
+
+    /// Drops the typing indicators that haven't been repeated in time.
+    pub fn expire_typing(&mut self, now: f64) -> Changes {
+        let mut changes = Changes::default();
+        for (chat_id, typing) in self.typing.iter_mut() {
+            let count = typing.len();
+            typing.retain(|(_, expires_at)| *expires_at > now);
+            if typing.len() != count {
+                changes.typing.insert(*chat_id);
+            }
+        }
+        self.typing.retain(|_, typing| !typing.is_empty());
+        changes
+    }
+
+    pub fn apply_event(&mut self, event: WsEvent) -> Changes {
+        match event {
+            WsEvent::Message(message) => self.apply_message(*message),
+            WsEvent::Chat(chat) => self.apply_chat(chat),
+            WsEvent::Receipt(receipt) => self.apply_receipt(receipt),
+            WsEvent::Reaction(update) => self.apply_reaction(update),
+            WsEvent::MessageHidden {
+                chat_id,
+                message_id,
+            } => self.hide_message(chat_id, &message_id),
+            WsEvent::Presence(presence) => self.apply_presence(presence),
+            WsEvent::Typing(update) => self.apply_typing(update, now_timestamp()),
+            WsEvent::Read(watermark) => self.apply_read_watermark(watermark),
+        }
+    }
+
+    fn advance_read_watermark(
+        &mut self,
+        chat_id: ChatId,
+        user_id: UserId,
+        seq: u64,
+        changes: &mut Changes,
+    ) -> bool {
+        let current = self
+            .read_watermarks
+            .entry(chat_id)
+            .or_default()
+            .entry(user_id)
+            .or_default();
+        if *current >= seq {
+            return false;
+        }
+        *current = seq;
+        changes.read.insert(chat_id);
+        true
+    }
+
+    fn stop_typing(&mut self, chat_id: ChatId, user_id: &UserId, changes: &mut Changes) {
+        let Some(typing) = self.typing.get_mut(&chat_id) else {
+            return;
+        };
+        let count = typing.len();
+        typing.retain(|(id, _)| id != user_id);
+        if typing.len() != count {
+            changes.typing.insert(chat_id);
+        }
+    }
+
+    fn is_unread(&self, message: &MessageModel) -> bool {
+        let Some(me) = self.me.as_ref() else {
+            return !message.is_read && !message.deleted && message.system.is_none();
+        };
+        let read_up_to = self
+            .read_watermarks
+            .get(&message.chat_id)
+            .and_then(|watermarks| watermarks.get(me));
+        !message.is_read
+            && !message.deleted
+            && message.system.is_none()
+            && &message.sender_id != me
+            && read_up_to.is_none_or(|seq| message.seq > *seq)
+    }
+
+    /// Brings the chat entry in line with its messages.
+    fn refresh_chat(&mut self, chat_id: ChatId, changes: &mut Changes) {
+        let Some(mut chat) = self.chat(chat_id).cloned() else {
+            return;
+        };
+        let unread_count = self
+            .messages(chat_id)
+            .iter()
+            .filter(|m| self.is_unread(m))
+            .count() as u32;
+        // the chat list sends the latest messages, so the last known one is the last message
+        let last_message = self
+            .messages(chat_id)
+            .last()
+            .cloned()
+            .or(chat.last_message.clone());
+        if chat.unread_count == unread_count && chat.last_message == last_message {
+            return;
+        }
+
+        let reorder = chat.last_activity() != last_message.as_ref().map_or(0.0, |m| m.created_at);
+        chat.unread_count = unread_count;
+        chat.last_message = last_message;
+        self.replace_chat(chat);
+        changes.chats.insert(chat_id);
+        changes.order |= reorder;
+    }
+
+    fn replace_chat(&mut self, chat: Chat) {
+        self.chats.insert(chat);
+    }
+}
+
+// pinning moves the chat, archiving moves it to another tab
+fn moves_in_list(old: &ChatSettings, new: &ChatSettings) -> bool {
+    old.pinned != new.pinned || old.archived != new.archived
+}
+
+// edits and deletions may arrive out of order, a deletion is final
+fn is_older_version(update: &MessageModel, current: &MessageModel) -> bool {
+    if current.deleted {
+        return !update.deleted;
+    }
+    !update.deleted && update.edited_at.unwrap_or(0.0) < current.edited_at.unwrap_or(0.0)
+}
+
+// the idempotency key also matches a message whose server id isn't known yet
+fn is_same_message(a: &MessageModel, b: &MessageModel) -> bool {
+    a.id == b.id || (a.client_id.is_some() && a.client_id == b.client_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::schemas::{NewMessage, PresenceStatus};
+
+    fn message(chat_id: ChatId, text: &str, created_at: f64) -> MessageModel {
+        MessageModel {
+            id: format!("{}-{}", chat_id, created_at),
+            seq: created_at as u64,
+            client_id: None,
+            chat_id,
+            sender_id: "u1".to_string(),
+            text: text.to_string(),
+            created_at,
+            is_read: false,
+            status: DeliveryStatus::Sent,
+            edited_at: None,
+            edits: vec![],
+            deleted: false,
+            reply_to: None,
+            reactions: vec![],
+            attachments: vec![],
+            system: None,
+        }
+    }
+
+    fn chat(id: ChatId, messages: Vec<MessageModel>) -> ChatModel {
+        ChatModel {
+            id,
+            name: None,
+            member_ids: vec!["u1".to_string(), "u2".to_string()],
+            messages,
+            read_watermarks: HashMap::new(),
+            roles: HashMap::new(),
+            settings: ChatSettings::default(),
+        }
+    }
+
+    #[test]
+    fn test_apply_chat_is_idempotent() {
+        let mut store = MessengerStore::default();
+        let chat = chat(1, vec![message(1, "hi", 10.0)]);
+
+        let changes = store.apply_chat(chat.clone());
+        assert!(changes.order);
+        assert_eq!(changes.messages, HashSet::from([1]));
+
+        assert!(store.apply_chat(chat).is_empty());
+        assert_eq!(store.messages(1).len(), 1);
+    }
+
+    #[test]
+    fn test_socket_message_after_api_is_deduplicated() {
+        let mut store = MessengerStore::default();
+        store.apply_chat(chat(1, vec![message(1, "hi", 10.0)]));
+
+        let changes = store.apply_event(WsEvent::Message(Box::new(message(1, "hi", 10.0))));
+
+        assert!(changes.is_empty());
+        assert_eq!(store.messages(1).len(), 1);
+    }
+
+    #[test]
+    fn test_new_message_moves_chat_up() {
+        let mut store = MessengerStore::default();
+        store.apply_chats(vec![
+            chat(1, vec![message(1, "old", 10.0)]),
+            chat(2, vec![message(2, "new", 20.0)]),
+        ]);
+        assert_eq!(store.chat_ids(), vec![2, 1]);
+
+        let changes = store.apply_message(message(1, "newest", 30.0));
+
+        assert_eq!(store.chat_ids(), vec![1, 2]);
+        assert_eq!(changes.chats, HashSet::from([1]));
+        assert_eq!(changes.messages, HashSet::from([1]));
+        assert_eq!(
+            store.chat(1).unwrap().last_message.as_ref().unwrap().text,
+            "newest"
+        );
+    }
+
+    #[test]
+    fn test_messages_are_ordered_by_time() {
+        let mut store = MessengerStore::default();
+        store.apply_chat(chat(1, vec![]));
+        store.apply_message(message(1, "second", 20.0));
+        store.apply_message(message(1, "first", 10.0));
+
+        let texts: Vec<&str> = store.messages(1).iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["first", "second"]);
+        assert_eq!(
+            store.chat(1).unwrap().last_message.as_ref().unwrap().text,
+            "second"
+        );
+    }
+
+    #[test]
+    fn test_messages_are_ordered_by_seq_not_time() {
+        let mut store = MessengerStore::default();
+        store.apply_chat(chat(1, vec![]));
+        // same timestamp, sent twice with the same text
+        let first = MessageModel {
+            seq: 1,
+            ..message(1, "hi", 10.0)
+        };
+        let second = MessageModel {
+            id: "second".to_string(),
+            seq: 2,
+            ..message(1, "hi", 10.0)
+        };
+        store.apply_message(second.clone());
+        store.apply_message(first.clone());
+
+        assert_eq!(store.messages(1), [first, second.clone()]);
+        assert_eq!(store.chat(1).unwrap().last_message, Some(second));
+    }
+
+    #[test]
+    fn test_message_is_deduplicated_by_client_id() {
+        let mut store = MessengerStore::default();
+        store.apply_chat(chat(1, vec![]));
+        let sent = MessageModel {
+            client_id: Some("key".to_string()),
+            ..message(1, "hi", 10.0)
+        };
+        store.apply_message(sent.clone());
+
+        let echo = MessageModel {
+            id: "other".to_string(),
+            ..sent
+        };
+        store.apply_message(echo);
+
+        assert_eq!(store.messages(1).len(), 1);
+        assert_eq!(store.messages(1)[0].id, "other");
+    }
+
+    #[test]
+    fn test_pending_message_is_replaced_by_server_copy() {
+        let mut store = MessengerStore {
+            me: Some("u1".to_string()),
+            ..Default::default()
+        };
+        store.apply_chat(chat(1, vec![message(1, "old", 10.0)]));
+        let new = NewMessage::new(1, "u1".to_string(), "hi".to_string());
+
+        store.apply_message(new.to_pending(20.0));
+        assert_eq!(store.messages(1)[1].status, DeliveryStatus::Pending);
+
+        store.mark_failed(1, &new.client_id);
+        assert_eq!(store.messages(1)[1].status, DeliveryStatus::Failed);
+
+        let retry = store.messages(1)[1].to_retry().unwrap();
+        assert_eq!(retry, new);
+        store.apply_message(retry.to_pending(21.0));
+        assert_eq!(store.messages(1)[1].status, DeliveryStatus::Pending);
+
+        let sent = MessageModel {
+            client_id: Some(new.client_id.clone()),
+            ..message(1, "hi", 22.0)
+        };
+        store.apply_message(sent.clone());
+
+        assert_eq!(store.messages(1), [message(1, "old", 10.0), sent.clone()]);
+        assert_eq!(store.chat(1).unwrap().last_message, Some(sent));
+        // an ack for a message that has been sent in the meantime changes nothing
+        assert!(store.mark_failed(1, &new.client_id).is_empty());
+    }
+
+    #[test]
+    fn test_receipts_only_move_status_forward() {
+        let mut store = MessengerStore {
+            me: Some("u1".to_string()),
+            ..Default::default()
+        };
+        store.apply_chat(chat(
+            1,
+            vec![
+                message(1, "a", 1.0),
+                message(1, "b", 2.0),
+                message(1, "c", 3.0),
+            ],
+        ));
+        let receipt = |seq, status| {
+            WsEvent::Receipt(DeliveryReceipt {
+                chat_id: 1,
+                seq,
+                status,
+            })
+        };
+
+        store.apply_event(receipt(2, DeliveryStatus::Read));
+        store.apply_event(receipt(3, DeliveryStatus::Delivered));
+        store.apply_message(message(1, "a", 1.0));
+
+        let statuses: Vec<DeliveryStatus> = store.messages(1).iter().map(|m| m.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                DeliveryStatus::Read,
+                DeliveryStatus::Read,
+                DeliveryStatus::Delivered
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edits_and_deletions_arriving_out_of_order() {
+        let mut store = MessengerStore::default();
+        store.apply_chat(chat(1, vec![message(1, "hi", 10.0)]));
+        let edit = |text: &str, edited_at: f64| MessageModel {
+            text: text.to_string(),
+            edited_at: Some(edited_at),
+            ..message(1, "hi", 10.0)
+        };
+
+        store.apply_message(edit("second", 30.0));
+        assert!(store.apply_message(edit("first", 20.0)).is_empty());
+        assert_eq!(store.messages(1)[0].text, "second");
+
+        let deleted = MessageModel {
+            text: String::new(),
+            deleted: true,
+            ..message(1, "hi", 10.0)
+        };
+        store.apply_message(deleted.clone());
+        assert!(store.apply_message(edit("third", 40.0)).is_empty());
+        assert_eq!(store.messages(1), [deleted]);
+        assert_eq!(store.total_unread(), 0);
+    }
+
+    #[test]
+    fn test_deleted_message_is_replaced_by_a_tombstone() {
+        let mut store = MessengerStore::default();
+        store.apply_chat(chat(
+            1,
+            vec![message(1, "a", 10.0), message(1, "secret", 20.0)],
+        ));
+        let id = store.messages(1)[1].id.clone();
+
+        let changes = store.delete_message(1, &id);
+        assert!(changes.chats.contains(&1));
+        let tombstone = &store.messages(1)[1];
+        assert!(tombstone.deleted);
+        assert!(tombstone.text.is_empty());
+        assert!(store.search_messages("secret", 10).is_empty());
+
+        // the server's copy arriving afterwards changes nothing
+        let echo = store.messages(1)[1].clone();
+        assert!(store.apply_message(echo).is_empty());
+        assert!(store.delete_message(1, &"missing".to_string()).is_empty());
+    }
+
+    #[test]
+    fn test_hidden_message_stays_hidden() {
+        let mut store = MessengerStore::default();
+        store.apply_chat(chat(1, vec![message(1, "a", 10.0), message(1, "b", 20.0)]));
+        let id = store.messages(1)[1].id.clone();
+
+        let changes = store.apply_event(WsEvent::MessageHidden {
+            chat_id: 1,
+            message_id: id,
+        });
+        assert!(changes.chats.contains(&1));
+        assert_eq!(
+            store.chat(1).unwrap().last_message.as_ref().unwrap().text,
+            "a"
+        );
+
+        store.apply_chat(chat(1, vec![message(1, "a", 10.0), message(1, "b", 20.0)]));
+        assert_eq!(store.messages(1).len(), 1);
+    }
+
+    #[test]
+    fn test_history_pages_go_before_loaded_messages() {
+        let mut store = MessengerStore::default();
+        store.apply_chat(chat(1, vec![message(1, "c", 30.0), message(1, "d", 40.0)]));
+        assert_eq!(store.oldest_seq(1), Some(30));
+        assert!(store.has_older_messages(1));
+
+        let page = MessagePage {
+            messages: vec![message(1, "b", 20.0)],
+            has_more: true,
+        };
+        store.apply_history(1, page);
+        assert_eq!(store.oldest_seq(1), Some(20));
+        assert!(store.has_older_messages(1));
+
+        let page = MessagePage {
+            messages: vec![message(1, "a", 10.0)],
+            has_more: false,
+        };
+        let changes = store.apply_history(1, page);
+
+        assert!(changes.messages.contains(&1));
+        assert!(!store.has_older_messages(1));
+        let texts: Vec<_> = store.messages(1).iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, ["a", "b", "c", "d"]);
+        assert_eq!(
+            store.chat(1).unwrap().last_message,
+            Some(message(1, "d", 40.0))
+        );
+    }
+
+    #[test]
+    fn test_reply_keeps_its_quote_when_sent() {
+        let mut store = MessengerStore {
+            me: Some("u1".to_string()),
+            ..Default::default()
+        };
+        let original = message(1, "question", 10.0);
+        store.apply_chat(chat(1, vec![original.clone()]));
+        let reply = NewMessage::new(1, "u1".to_string(), "answer".to_string())
+            .with_reply_to(Some(original.to_quote()));
+
+        store.apply_message(reply.to_pending(20.0));
+        store.mark_failed(1, &reply.client_id);
+
+        let retry = store.messages(1)[1].to_retry().unwrap();
+        assert_eq!(retry.reply_to.unwrap().id, original.id);
+    }
+
+    #[test]
+    fn test_reactions_are_counted_once_per_user() {
+        let mut store = MessengerStore::default();
+        store.apply_chat(chat(1, vec![message(1, "ship it?", 10.0)]));
+        let update = |user_id: &str, emoji: &str, added| {
+            WsEvent::Reaction(ReactionUpdate {
+                chat_id: 1,
+                message_id: "1-10".to_string(),
+                user_id: user_id.to_string(),
+                emoji: emoji.to_string(),
+                added,
+            })
+        };
+
+        store.apply_event(update("u1", "👍", true));
+        store.apply_event(update("u2", "👍", true));
+        store.apply_event(update("u2", "🎉", true));
+        assert!(store.apply_event(update("u2", "👍", true)).is_empty());
+        let reactions = &store.messages(1)[0].reactions;
+        assert_eq!(reactions.len(), 2);
+        assert_eq!(reactions[0].count, 2);
+        assert_eq!(reactions[0].user_ids, ["u1", "u2"]);
+
+        store.apply_event(update("u2", "🎉", false));
+        assert!(store.apply_event(update("u2", "🎉", false)).is_empty());
+        store.apply_event(update("u1", "👍", false));
+        let reactions = &store.messages(1)[0].reactions;
+        assert_eq!(reactions.len(), 1);
+        assert_eq!(reactions[0].count, 1);
+        assert_eq!(reactions[0].user_ids, ["u2"]);
+    }
+
+    #[test]
+    fn test_typing_expires_and_stops_with_the_message() {
+        let mut store = MessengerStore {
+            me: Some("u1".to_string()),
+            ..Default::default()
+        };
+        store.apply_chat(chat(1, vec![]));
+        let typing = |user_id: &str, typing| TypingUpdate {
+            chat_id: 1,
+            user_id: user_id.to_string(),
+            typing,
+        };
+
+        assert!(store.apply_typing(typing("u1", true), 0.0).is_empty());
+        assert!(!store.apply_typing(typing("u2", true), 0.0).is_empty());
+        assert!(!store.apply_typing(typing("u3", true), 0.0).is_empty());
+        // repeating only extends it
+        assert!(store.apply_typing(typing("u2", true), 4.0).is_empty());
+        assert_eq!(store.typing_users(1), ["u2", "u3"]);
+
+        let changes = store.expire_typing(TYPING_TIMEOUT + 1.0);
+        assert!(changes.typing.contains(&1));
+        assert_eq!(store.typing_users(1), ["u2"]);
+
+        let sent = MessageModel {
+            sender_id: "u2".to_string(),
+            ..message(1, "done", 10.0)
+        };
+        let changes = store.apply_message(sent);
+        assert!(changes.typing.contains(&1));
+        assert!(store.typing_users(1).is_empty());
+    }
+
+    #[test]
+    fn test_presence_keeps_last_seen() {
+        let mut store = MessengerStore::default();
+        let presence = |status, last_seen| {
+            WsEvent::Presence(Presence {
+                user_id: "u2".to_string(),
+                status,
+                last_seen,
+            })
+        };
+
+        store.apply_event(presence(PresenceStatus::Offline, Some(100.0)));
+        let changes = store.apply_event(presence(PresenceStatus::Online, None));
+        assert!(changes.presence);
+        assert!(
+            store
+                .apply_event(presence(PresenceStatus::Online, None))
+                .is_empty()
+        );
+
+        let current = store.presence(&"u2".to_string()).unwrap();
+        assert_eq!(current.status, PresenceStatus::Online);
+        assert_eq!(current.last_seen, Some(100.0));
+    }
+
+    #[test]
+    fn test_missing_users_and_titles() {
+        let mut store = MessengerStore {
+            me: Some("u1".to_string()),
+            ..Default::default()
+        };
+        store.apply_chat(chat(1, vec![]));
+        assert_eq!(
+            store.missing_user_ids(),
+            vec!["u1".to_string(), "u2".to_string()]
+        );
+
+        store.apply_users(vec![User {
+            id: "u2".to_string(),
+            username: "jane".to_string(),
+        }]);
+
+        assert_eq!(store.missing_user_ids(), vec!["u1".to_string()]);
+        assert_eq!(store.chat_title(store.chat(1).unwrap()), "jane");
+    }
+
+    #[test]
+    fn test_unread_count_and_mark_read() {
+        let mut store = MessengerStore {
+            me: Some("u2".to_string()),
+            ..Default::default()
+        };
+        let mine = MessageModel {
+            sender_id: "u2".to_string(),
+            ..message(1, "mine", 15.0)
+        };
+        store.apply_chat(chat(
+            1,
+            vec![message(1, "a", 10.0), mine, message(1, "b", 20.0)],
+        ));
+        assert_eq!(store.chat(1).unwrap().unread_count, 2);
+        assert_eq!(store.first_unread(1), Some(0));
+        assert_eq!(store.total_unread(), 2);
+
+        let changes = store.mark_read(1);
+
+        assert_eq!(changes.chats, HashSet::from([1]));
+        assert_eq!(store.chat(1).unwrap().unread_count, 0);
+        assert_eq!(store.first_unread(1), None);
+        assert!(
+            store
+                .chat(1)
+                .unwrap()
+                .last_message
+                .as_ref()
+                .unwrap()
+                .is_read
+        );
+        assert!(store.mark_read(1).is_empty());
+    }
+
+    #[test]
+    fn test_mark_read_skips_unsent_messages() {
+        let mut store = MessengerStore {
+            me: Some("u2".to_string()),
+            ..Default::default()
+        };
+        store.apply_chat(chat(1, vec![message(1, "a", 10.0)]));
+        let new = NewMessage::new(1, "u2".to_string(), "hi".to_string());
+        store.apply_message(new.to_pending(15.0));
+        store.mark_failed(1, &new.client_id);
+
+        store.mark_read(1);
+        assert_eq!(store.read_watermarks(1).get("u2"), Some(&10));
+
+        store.apply_message(message(1, "b", 20.0));
+        assert_eq!(store.chat(1).unwrap().unread_count, 1);
+        assert_eq!(store.total_unread(), 1);
+    }
+
+    #[test]
+    fn test_read_state_from_server_replaces_message() {
+        let mut store = MessengerStore::default();
+        store.apply_chat(chat(1, vec![message(1, "hi", 10.0)]));
+        assert_eq!(store.total_unread(), 1);
+
+        let read = MessageModel {
+            is_read: true,
+            ..message(1, "hi", 10.0)
+        };
+        store.apply_message(read);
+
+        assert_eq!(store.messages(1).len(), 1);
+        assert_eq!(store.total_unread(), 0);
+    }
+
+    #[test]
+    fn test_read_watermarks_only_move_forward() {
+        let mut store = MessengerStore {
+            me: Some("u2".to_string()),
+            ..Default::default()
+        };
+        let mine = MessageModel {
+            sender_id: "u2".to_string(),
+            ..message(1, "mine", 10.0)
+        };
+        store.apply_chat(ChatModel {
+            member_ids: vec!["u1".into(), "u2".into(), "u3".into()],
+            read_watermarks: HashMap::from([("u3".to_string(), 10)]),
+            ..chat(1, vec![mine.clone(), message(1, "reply", 20.0)])
+        });
+        assert_eq!(store.readers(&mine), ["u3"]);
+
+        let watermark = |user_id: &str, seq| ReadWatermark {
+            chat_id: 1,
+            user_id: user_id.to_string(),
+            seq,
+        };
+        let changes = store.apply_event(WsEvent::Read(watermark("u1", 20)));
+        assert_eq!(changes.read, HashSet::from([1]));
+        assert_eq!(store.readers(&mine), ["u1", "u3"]);
+        // the sender isn't listed as a reader of their own message
+        assert!(store.readers(&store.messages(1)[1].clone()).is_empty());
+
+        assert!(store.apply_read_watermark(watermark("u1", 15)).is_empty());
+        assert_eq!(store.read_watermarks(1)["u1"], 20);
+    }
+
+    #[test]
+    fn test_own_read_watermark_marks_messages_read() {
+        let mut store = MessengerStore {
+            me: Some("u2".to_string()),
+            ..Default::default()
+        };
+        store.apply_chat(chat(1, vec![message(1, "a", 10.0), message(1, "b", 20.0)]));
+        assert_eq!(store.chat(1).unwrap().unread_count, 2);
+
+        // read on another device
+        let changes = store.apply_read_watermark(ReadWatermark {
+            chat_id: 1,
+            user_id: "u2".to_string(),
+            seq: 10,
+        });
+
+        assert_eq!(changes.chats, HashSet::from([1]));
+        assert_eq!(store.chat(1).unwrap().unread_count, 1);
+        assert_eq!(store.first_unread(1), Some(1));
+
+        store.mark_read(1);
+        assert_eq!(store.read_watermarks(1)["u2"], 20);
+    }
+
+    #[test]
+    fn test_removed_member_loses_the_chat() {
+        let mut store = MessengerStore {
+            me: Some("u2".to_string()),
+            ..Default::default()
+        };
+        store.apply_chats(vec![
+            chat(1, vec![message(1, "hi", 10.0)]),
+            chat(2, vec![message(2, "hi", 20.0)]),
+        ]);
+
+        let changes = store.apply_event(WsEvent::Chat(ChatModel {
+            member_ids: vec!["u1".to_string()],
+            ..chat(1, vec![])
+        }));
+
+        assert!(changes.order);
+        assert_eq!(store.chat_ids(), vec![2]);
+        assert!(store.messages(1).is_empty());
+        assert!(store.remove_chat(1).is_empty());
+    }
+
+    #[test]
+    fn test_system_messages() {
+        let mut store = MessengerStore {
+            me: Some("u2".to_string()),
+            ..Default::default()
+        };
+        store.apply_users(vec![
+            User {
+                id: "u1".to_string(),
+                username: "jane".to_string(),
+            },
+            User {
+                id: "u3".to_string(),
+                username: "bob".to_string(),
+            },
+        ]);
+        let added = MessageModel {
+            system: Some(SystemEvent::MembersAdded {
+                user_ids: vec!["u3".to_string()],
+            }),
+            ..message(1, "", 10.0)
+        };
+        store.apply_chat(chat(1, vec![added.clone()]));
+
+        assert_eq!(store.system_text(&added).unwrap(), "jane added bob");
+        assert_eq!(store.chat(1).unwrap().unread_count, 0);
+        assert_eq!(store.system_text(&message(1, "hi", 20.0)), None);
+    }
+
+    #[test]
+    fn test_pinned_chats_come_first_and_muted_ones_dont_count() {
+        let mut store = MessengerStore::default();
+        store.apply_chats(vec![
+            chat(1, vec![message(1, "old", 10.0)]),
+            chat(2, vec![message(2, "new", 20.0)]),
+        ]);
+        assert_eq!(store.chat_ids(), vec![2, 1]);
+
+        let pinned = ChatSettings {
+            pinned: true,
+            ..Default::default()
+        };
+        let changes = store.apply_chat_settings(1, pinned);
+
+        assert!(changes.order);
+        assert_eq!(store.chat_ids(), vec![1, 2]);
+        store.apply_message(message(2, "newer", 30.0));
+        assert_eq!(store.chat_ids(), vec![1, 2]);
+        assert_eq!(store.total_unread(), 3);
+
+        let muted = ChatSettings {
+            muted: true,
+            ..Default::default()
+        };
+        let changes = store.apply_chat_settings(2, muted.clone());
+        assert!(!changes.order);
+        assert_eq!(store.total_unread(), 1);
+        assert!(store.apply_chat_settings(2, muted).is_empty());
+
+        let expired = ChatSettings {
+            muted: true,
+            muted_until: Some(1.0),
+            ..Default::default()
+        };
+        store.apply_chat_settings(2, expired);
+        assert_eq!(store.total_unread(), 3);
+    }
+
+    #[test]
+    fn test_archived_chats_and_folders() {
+        let mut store = MessengerStore::default();
+        store.apply_chats(vec![
+            chat(1, vec![message(1, "a", 10.0)]),
+            ChatModel {
+                settings: ChatSettings {
+                    archived: true,
+                    ..Default::default()
+                },
+                ..chat(2, vec![message(2, "b", 20.0)])
+            },
+        ]);
+        assert_eq!(store.filtered_chat_ids(&ChatFilter::All), vec![1]);
+        assert_eq!(store.filtered_chat_ids(&ChatFilter::Archived), vec![2]);
+
+        let mut work = ChatFolder {
+            id: "work".to_string(),
+            name: "Work".to_string(),
+            chat_ids: vec![],
+            include_unread: false,
+        };
+        assert!(store.apply_folder(work.clone()).folders);
+        let filter = ChatFilter::Folder("work".to_string());
+        assert!(store.filtered_chat_ids(&filter).is_empty());
+
+        work.chat_ids.push(1);
+        store.apply_folder(work.clone());
+        assert_eq!(store.filtered_chat_ids(&filter), vec![1]);
+        assert!(store.apply_folders(vec![work]).is_empty());
+        assert!(store.remove_folder("work").folders);
+        assert!(store.folders().is_empty());
+    }
+
+    #[test]
+    fn test_search_follows_edits_and_hidden_messages() {
+        let mut store = MessengerStore::default();
+        store.apply_chat(chat(
+            1,
+            vec![
+                message(1, "lunch today?", 10.0),
+                message(1, "Lunch at noon", 20.0),
+            ],
+        ));
+
+        let found: Vec<&str> = store
+            .search_messages("lunch", 10)
+            .iter()
+            .map(|m| m.text.as_str())
+            .collect();
+        assert_eq!(found, ["Lunch at noon", "lunch today?"]);
+
+        let edited = MessageModel {
+            text: "dinner today?".to_string(),
+            edited_at: Some(30.0),
+            ..message(1, "lunch today?", 10.0)
+        };
+        store.apply_message(edited);
+        store.hide_message(1, &"1-20".to_string());
+
+        assert!(store.search_messages("lunch", 10).is_empty());
+        assert_eq!(store.search_messages("dinn", 10).len(), 1);
+    }
+
+    #[test]
+    fn test_select_next_and_previous() {
+        let mut store = MessengerStore::default();
+        store.apply_chats(vec![
+            chat(1, vec![message(1, "old", 10.0)]),
+            chat(2, vec![message(2, "new", 20.0)]),
+        ]);
+
+        assert_eq!(store.select_next(), Some(2));
+        assert_eq!(store.select_next(), Some(1));
+        assert_eq!(store.select_next(), Some(2));
+
+        store.select_chat(Some(1));
+        assert_eq!(store.select_previous(), Some(2));
+
+        store.select_chat(Some(42));
+        assert_eq!(store.selected_chat_id(), None);
+    }
+
+    #[test]
+    fn test_direct_chat_with() {
+        let mut store = MessengerStore {
+            me: Some("u1".to_string()),
+            ..Default::default()
+        };
+        let mut group = chat(2, vec![]);
+        group.name = Some("Team".to_string());
+        store.apply_chats(vec![group, chat(1, vec![])]);
+
+        assert_eq!(store.direct_chat_with(&"u2".to_string()), Some(1));
+        assert_eq!(store.direct_chat_with(&"u3".to_string()), None);
+    }
+}
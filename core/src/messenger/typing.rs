@@ -0,0 +1,49 @@
+/// Seconds after which a typing indicator goes away, unless the user is still typing.
+pub const TYPING_TIMEOUT: f64 = 6.0;
+/// While the user keeps typing, the others are reminded this often, in seconds.
+pub const TYPING_INTERVAL: f64 = 3.0;
+
+/// Decides when to tell the other members that the user is typing.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TypingThrottle {
+    sent_at: Option<f64>,
+}
+
+impl TypingThrottle {
+    /// Whether a keystroke should be sent as a typing event.
+    pub fn on_input(&mut self, now: f64) -> bool {
+        if self
+            .sent_at
+            .is_some_and(|sent_at| now - sent_at < TYPING_INTERVAL)
+        {
+            return false;
+        }
+        self.sent_at = Some(now);
+        true
+    }
+
+    /// Whether to send that the user has stopped, e.g. after sending or clearing the input.
+    pub fn on_stop(&mut self) -> bool {
+        self.sent_at.take().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typing_is_throttled() {
+        let mut throttle = TypingThrottle::default();
+        assert!(!throttle.on_stop());
+
+        assert!(throttle.on_input(10.0));
+        assert!(!throttle.on_input(11.0));
+        assert!(!throttle.on_input(12.9));
+        assert!(throttle.on_input(13.0));
+
+        assert!(throttle.on_stop());
+        assert!(!throttle.on_stop());
+        assert!(throttle.on_input(13.5));
+    }
+}
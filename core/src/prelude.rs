@@ -1,2 +1,2 @@
 pub use crate::traits::AuthState;
-pub use crate::storage::SharedStorage;
+pub use crate::storage::{InvalidAppName, ScopedStorage, SharedStorage};
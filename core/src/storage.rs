@@ -1,7 +1,12 @@
 use crate::auth::schemas::Auth;
+use crate::helpers::types::UserId;
 use crate::traits::Storage;
+use std::fmt;
 use std::sync::{Arc, RwLock};
 
+const APP_SCOPE_PREFIX: &str = "app:";
+const APP_SCOPE_SEPARATOR: char = ':';
+
 #[derive(Clone)]
 pub struct SharedStorage(Arc<RwLock<dyn Storage + Send + Sync>>);
 
@@ -21,6 +26,74 @@ impl SharedStorage {
     pub fn remove(&self, key: &str) {
         self.0.write().unwrap().remove(key);
     }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.0.read().unwrap().keys()
+    }
+
+    /// Storage view of a single app, its keys can't reach outside of the app namespace.
+    pub fn scoped(&self, app_name: &str) -> Result<ScopedStorage, InvalidAppName> {
+        if app_name.is_empty() || app_name.contains(APP_SCOPE_SEPARATOR) {
+            return Err(InvalidAppName(app_name.to_string()));
+        }
+        Ok(ScopedStorage {
+            storage: self.clone(),
+            prefix: format!("{APP_SCOPE_PREFIX}{app_name}{APP_SCOPE_SEPARATOR}"),
+        })
+    }
+}
+
+/// App names are storage namespaces, so they can't be empty or contain the separator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidAppName(pub String);
+
+impl fmt::Display for InvalidAppName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid app name \"{}\", it can't be empty or contain '{}'",
+            self.0, APP_SCOPE_SEPARATOR
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct ScopedStorage {
+    storage: SharedStorage,
+    prefix: String,
+}
+
+impl ScopedStorage {
+    pub fn set(&self, key: &str, value: &str) {
+        self.storage.set(&self.key(key), value);
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.storage.get(&self.key(key))
+    }
+
+    pub fn remove(&self, key: &str) {
+        self.storage.remove(&self.key(key));
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.storage
+            .keys()
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&self.prefix).map(str::to_string))
+            .collect()
+    }
+
+    /// Removes everything the app has stored, other apps and core data are untouched.
+    pub fn clear(&self) {
+        for key in self.keys() {
+            self.remove(&key);
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
 }
 
 pub struct AuthManager {
@@ -47,6 +120,10 @@ impl AuthManager {
         Some(Auth::new(&access_token.unwrap(), &refresh_token.unwrap()))
     }
 
+    pub fn get_user_id(&self) -> Option<UserId> {
+        self.storage.get("user_id")
+    }
+
     pub fn update_auth(&mut self, auth: Auth) {
         self.storage.set("refresh_token", &auth.refresh_token);
         self.storage.set("access_token", &auth.access_token);
@@ -57,3 +134,74 @@ impl AuthManager {
         self.storage.remove("access_token");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct MemoryStorage(RwLock<HashMap<String, String>>);
+
+    impl Storage for MemoryStorage {
+        fn set(&self, key: &str, value: &str) {
+            self.0
+                .write()
+                .unwrap()
+                .insert(key.to_string(), value.to_string());
+        }
+
+        fn get(&self, key: &str) -> Option<String> {
+            self.0.read().unwrap().get(key).cloned()
+        }
+
+        fn remove(&self, key: &str) {
+            self.0.write().unwrap().remove(key);
+        }
+
+        fn keys(&self) -> Vec<String> {
+            self.0.read().unwrap().keys().cloned().collect()
+        }
+    }
+
+    #[test]
+    fn test_scoped_storage_is_isolated() {
+        let storage = SharedStorage::new(MemoryStorage::default());
+        storage.set("refresh_token", "secret");
+        let messenger = storage.scoped("messenger").unwrap();
+        let notes = storage.scoped("notes").unwrap();
+
+        messenger.set("draft", "hello");
+        notes.set("draft", "todo");
+
+        assert_eq!(messenger.get("refresh_token"), None);
+        assert_eq!(messenger.get("draft"), Some("hello".to_string()));
+        assert_eq!(notes.get("draft"), Some("todo".to_string()));
+        assert_eq!(messenger.keys(), vec!["draft".to_string()]);
+    }
+
+    #[test]
+    fn test_scoped_storage_clear() {
+        let storage = SharedStorage::new(MemoryStorage::default());
+        storage.set("access_token", "secret");
+        let messenger = storage.scoped("messenger").unwrap();
+        let notes = storage.scoped("notes").unwrap();
+        messenger.set("draft", "hello");
+        messenger.set("last_chat", "3");
+        notes.set("draft", "todo");
+
+        messenger.clear();
+
+        assert!(messenger.keys().is_empty());
+        assert_eq!(notes.get("draft"), Some("todo".to_string()));
+        assert_eq!(storage.get("access_token"), Some("secret".to_string()));
+    }
+
+    #[test]
+    fn test_scoped_storage_rejects_invalid_names() {
+        let storage = SharedStorage::new(MemoryStorage::default());
+
+        assert!(storage.scoped("messenger:draft").is_err());
+        assert!(storage.scoped("").is_err());
+    }
+}
@@ -5,6 +5,7 @@ pub trait Storage {
     fn set(&self, key: &str, value: &str);
     fn get(&self, key: &str) -> Option<String>;
     fn remove(&self, key: &str);
+    fn keys(&self) -> Vec<String>;
 }
 
 pub trait ToJson {
@@ -1,3 +1,5 @@
+use chrono::{DateTime, Datelike, Local, TimeZone};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
 use serde::Deserialize;
 use std::collections::HashMap;
 
@@ -16,6 +18,62 @@ pub fn from_map<T: for<'de> Deserialize<'de>>(map: &HashMap<String, String>) ->
     serde_json::from_value(serde_json::json!(map)).map_err(|e| e.to_string())
 }
 
+/// Current unix timestamp in seconds, the unit of the message service.
+pub fn now_timestamp() -> f64 {
+    chrono::Utc::now().timestamp_millis() as f64 / 1000.0
+}
+
+/// Short local time of a unix timestamp in seconds, like a chat list shows it:
+/// "14:32" for today, "12 Mar" for this year and "12.03.2023" before that.
+pub fn format_timestamp(timestamp: f64) -> String {
+    format_timestamp_at(timestamp, Local::now())
+}
+
+fn format_timestamp_at<Tz: TimeZone>(timestamp: f64, now: DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let Some(time) = DateTime::from_timestamp_millis((timestamp * 1000.0) as i64) else {
+        return String::new();
+    };
+    let time = time.with_timezone(&now.timezone());
+
+    if time.date_naive() == now.date_naive() {
+        time.format("%H:%M").to_string()
+    } else if time.year() == now.year() {
+        time.format("%d %b").to_string()
+    } else {
+        time.format("%d.%m.%Y").to_string()
+    }
+}
+
+/// File size for humans, like "340 B", "1.2 KB" or "5.0 MB".
+pub fn format_file_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if size < 1024 {
+        return format!("{} B", size);
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+// everything but the unreserved characters, so a segment can't contain `/`, `?` or `#`
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Percent-encodes an id for use as a single url path segment.
+pub fn encode_path_segment(segment: &str) -> String {
+    utf8_percent_encode(segment, PATH_SEGMENT).to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -68,7 +126,7 @@ mod tests {
 
         let result: Result<UserProfile, String> = from_map(&map);
         assert!(result.is_err());
-        assert!(result.err().unwrap().contains("invalid digit"));
+        assert!(result.err().unwrap().contains("invalid type"));
     }
 
     #[test]
@@ -80,6 +138,29 @@ mod tests {
         assert!(result.err().unwrap().contains("missing field `name`"));
     }
 
+    #[test]
+    fn test_format_timestamp() {
+        let now = chrono::Utc.with_ymd_and_hms(2025, 3, 12, 18, 0, 0).unwrap();
+
+        assert_eq!(format_timestamp_at(1741789920.5, now), "14:32");
+        assert_eq!(format_timestamp_at(1741357920.0, now), "07 Mar");
+        assert_eq!(format_timestamp_at(1678631520.0, now), "12.03.2023");
+    }
+
+    #[test]
+    fn test_encode_path_segment() {
+        assert_eq!(encode_path_segment("a1-b_c.d~e"), "a1-b_c.d~e");
+        assert_eq!(encode_path_segment("../x?y#z"), "..%2Fx%3Fy%23z");
+        assert_eq!(encode_path_segment("a b%"), "a%20b%25");
+    }
+
+    #[test]
+    fn test_format_file_size() {
+        assert_eq!(format_file_size(340), "340 B");
+        assert_eq!(format_file_size(1229), "1.2 KB");
+        assert_eq!(format_file_size(5 * 1024 * 1024), "5.0 MB");
+    }
+
     #[test]
     fn test_macro_f() {
         let name = "Alice";
@@ -0,0 +1,184 @@
+//! Typed publish/subscribe between apps and the shell.
+//!
+//! Any `Clone + 'static` type becomes an event with `impl Event for MyEvent {}`. Handlers run
+//! synchronously on publish, in subscription order. A subscription lives until its
+//! [`Subscription`] is dropped, [`use_event`] ties it to the component instead.
+
+use dioxus::prelude::*;
+use lcore::api::schemas::{WsCommand, WsEvent};
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub trait Event: Any + Clone {}
+
+/// Published after the user has logged in or a stored session was restored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoggedIn;
+
+impl Event for LoggedIn {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoggedOut;
+
+impl Event for LoggedOut {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AppActivated {
+    pub name: String,
+}
+
+impl Event for AppActivated {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AppDeactivated {
+    pub name: String,
+}
+
+impl Event for AppDeactivated {}
+
+/// Pushed by the message service, published by the platform's socket connection.
+impl Event for WsEvent {}
+
+/// Published by apps, sent by the platform's socket connection.
+impl Event for WsCommand {}
+
+type Handler = Rc<dyn Fn(&dyn Any)>;
+
+thread_local! {
+    // the UI runs on a single thread, including spawned tasks
+    static SUBSCRIBERS: RefCell<HashMap<TypeId, Vec<(u64, Handler)>>> = RefCell::new(HashMap::new());
+    static NEXT_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Unsubscribes when dropped.
+pub struct Subscription {
+    type_id: TypeId,
+    id: u64,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        // the thread may already be shutting down
+        let _ = SUBSCRIBERS.try_with(|subscribers| {
+            if let Some(handlers) = subscribers.borrow_mut().get_mut(&self.type_id) {
+                handlers.retain(|(id, _)| *id != self.id);
+            }
+        });
+    }
+}
+
+#[must_use = "the handler is unsubscribed when the subscription is dropped"]
+pub fn subscribe<E: Event>(handler: impl Fn(&E) + 'static) -> Subscription {
+    let id = NEXT_ID.with(|next| {
+        let id = next.get();
+        next.set(id + 1);
+        id
+    });
+    let handler: Handler = Rc::new(move |event: &dyn Any| {
+        if let Some(event) = event.downcast_ref::<E>() {
+            handler(event);
+        }
+    });
+    SUBSCRIBERS.with(|subscribers| {
+        subscribers
+            .borrow_mut()
+            .entry(TypeId::of::<E>())
+            .or_default()
+            .push((id, handler));
+    });
+
+    Subscription {
+        type_id: TypeId::of::<E>(),
+        id,
+    }
+}
+
+pub fn publish<E: Event>(event: E) {
+    // handlers may (un)subscribe or publish themselves, so don't hold the borrow while calling
+    let handlers: Vec<Handler> = SUBSCRIBERS.with(|subscribers| {
+        subscribers
+            .borrow()
+            .get(&TypeId::of::<E>())
+            .map(|handlers| handlers.iter().map(|(_, h)| h.clone()).collect())
+            .unwrap_or_default()
+    });
+    for handler in handlers {
+        handler(&event);
+    }
+}
+
+/// Subscribes for the lifetime of the component.
+pub fn use_event<E: Event>(handler: impl FnMut(E) + 'static) {
+    use_hook(|| {
+        let handler = RefCell::new(handler);
+        Rc::new(subscribe(move |event: &E| {
+            // a handler publishing the same event type would re-enter itself
+            if let Ok(mut handler) = handler.try_borrow_mut() {
+                handler(event.clone());
+            }
+        }))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Ping(u32);
+
+    impl Event for Ping {}
+
+    #[derive(Clone)]
+    struct Pong;
+
+    impl Event for Pong {}
+
+    #[test]
+    fn test_publish_reaches_subscribers_of_the_type() {
+        let received = Rc::new(RefCell::new(vec![]));
+        let pongs = Rc::new(Cell::new(0));
+
+        let r = received.clone();
+        let _ping = subscribe(move |e: &Ping| r.borrow_mut().push(e.0));
+        let p = pongs.clone();
+        let _pong = subscribe(move |_: &Pong| p.set(p.get() + 1));
+
+        publish(Ping(1));
+        publish(Ping(2));
+
+        assert_eq!(*received.borrow(), vec![1, 2]);
+        assert_eq!(pongs.get(), 0);
+    }
+
+    #[test]
+    fn test_dropped_subscription_is_not_called() {
+        let count = Rc::new(Cell::new(0));
+
+        let c = count.clone();
+        let subscription = subscribe(move |_: &Pong| c.set(c.get() + 1));
+        publish(Pong);
+        drop(subscription);
+        publish(Pong);
+
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn test_handler_can_publish() {
+        let count = Rc::new(Cell::new(0));
+
+        let _ping = subscribe(|e: &Ping| {
+            if e.0 > 0 {
+                publish(Ping(e.0 - 1));
+            }
+        });
+        let c = count.clone();
+        let _counter = subscribe(move |_: &Ping| c.set(c.get() + 1));
+        publish(Ping(2));
+
+        assert_eq!(count.get(), 3);
+    }
+}
@@ -0,0 +1,367 @@
+//! Keyboard shortcuts.
+//!
+//! Every scope has a keymap of named actions with their default key combos: [`GLOBAL_SCOPE`]
+//! for the shell and one scope per app, named after it. The defaults can be overridden in the
+//! `[keybindings.<scope>]` tables of the config. A key press is resolved in the active app's
+//! keymap first, then in the global one, and the action is published as a [`KeyAction`].
+
+use crate::events::{Event, publish, use_event};
+use crate::state::app::ACTIVE_APP;
+use dioxus::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+pub const GLOBAL_SCOPE: &str = "global";
+
+/// Default bindings of a scope, e.g. `&[("next_chat", "Alt+ArrowDown")]`.
+pub type KeymapDefaults = &'static [(&'static str, &'static str)];
+
+/// Published when a bound key combo is pressed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyAction {
+    pub scope: String,
+    pub action: String,
+}
+
+impl Event for KeyAction {}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyModifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub meta: bool,
+}
+
+/// A key with modifiers, written like `Ctrl+Shift+K` or `Alt+ArrowDown`.
+///
+/// The key is either a `KeyboardEvent.key` name or a character, compared case-insensitively.
+/// Letters and digits also match by physical key, so `Alt+1` works on layouts where Alt
+/// changes the character.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyCombo {
+    pub key: String,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyCombo {
+    pub fn parse(combo: &str) -> Result<Self, KeyError> {
+        let mut modifiers = KeyModifiers::default();
+        let mut key = None;
+        for part in combo.split('+').map(str::trim) {
+            match part.to_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "alt" | "option" => modifiers.alt = true,
+                "shift" => modifiers.shift = true,
+                "meta" | "cmd" | "super" => modifiers.meta = true,
+                "" => return Err(KeyError::InvalidCombo(combo.to_string())),
+                _ if key.is_some() => return Err(KeyError::InvalidCombo(combo.to_string())),
+                _ => key = Some(part.to_string()),
+            }
+        }
+        let key = key.ok_or_else(|| KeyError::InvalidCombo(combo.to_string()))?;
+
+        Ok(Self { key, modifiers })
+    }
+
+    /// `key` and `code` as in the DOM `KeyboardEvent`.
+    pub fn matches(&self, key: &str, code: &str, modifiers: KeyModifiers) -> bool {
+        if self.modifiers != modifiers {
+            return false;
+        }
+        if self.key.eq_ignore_ascii_case(key) {
+            return true;
+        }
+        let physical = match self.key.chars().next() {
+            Some(c) if self.key.len() == 1 && c.is_ascii_alphabetic() => {
+                format!("Key{}", c.to_ascii_uppercase())
+            }
+            Some(c) if self.key.len() == 1 && c.is_ascii_digit() => format!("Digit{}", c),
+            _ => return false,
+        };
+        physical == code
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let m = &self.modifiers;
+        for (enabled, name) in [
+            (m.ctrl, "Ctrl"),
+            (m.alt, "Alt"),
+            (m.shift, "Shift"),
+            (m.meta, "Meta"),
+        ] {
+            if enabled {
+                write!(f, "{}+", name)?;
+            }
+        }
+        if self.key.len() == 1 {
+            write!(f, "{}", self.key.to_uppercase())
+        } else {
+            write!(f, "{}", self.key)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyError {
+    InvalidCombo(String),
+    UnknownAction { scope: String, action: String },
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyError::InvalidCombo(combo) => write!(f, "Invalid key combo: {}", combo),
+            KeyError::UnknownAction { scope, action } => {
+                write!(f, "Unknown key action {} in {}", action, scope)
+            }
+        }
+    }
+}
+
+/// Actions and their key combos, by scope.
+#[derive(Debug, Default)]
+pub struct Keymaps {
+    scopes: HashMap<String, Vec<(String, Vec<KeyCombo>)>>,
+}
+
+impl Keymaps {
+    /// Replaces the keymap of the scope, returns the defaults that couldn't be parsed.
+    ///
+    /// Their actions are left unbound, the config can still bind them.
+    pub fn register(&mut self, scope: &str, defaults: KeymapDefaults) -> Vec<KeyError> {
+        let mut errors = vec![];
+        let keymap = defaults
+            .iter()
+            .map(|(action, combo)| {
+                let combos = match KeyCombo::parse(combo) {
+                    Ok(combo) => vec![combo],
+                    Err(e) => {
+                        errors.push(e);
+                        vec![]
+                    }
+                };
+                (action.to_string(), combos)
+            })
+            .collect();
+        self.scopes.insert(scope.to_string(), keymap);
+        errors
+    }
+
+    /// Overrides the combos of registered actions, returns what couldn't be applied.
+    pub fn configure(
+        &mut self,
+        bindings: &HashMap<String, HashMap<String, Vec<String>>>,
+    ) -> Vec<KeyError> {
+        let mut errors = vec![];
+        for (scope, actions) in bindings {
+            for (action, combos) in actions {
+                let entry = self
+                    .scopes
+                    .get_mut(scope)
+                    .and_then(|keymap| keymap.iter_mut().find(|(a, _)| a == action));
+                let Some((_, keys)) = entry else {
+                    errors.push(KeyError::UnknownAction {
+                        scope: scope.clone(),
+                        action: action.clone(),
+                    });
+                    continue;
+                };
+                match combos.iter().map(|c| KeyCombo::parse(c)).collect() {
+                    Ok(combos) => *keys = combos,
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+        errors
+    }
+
+    /// Action bound to the key in the active app's keymap, or else the global one.
+    pub fn resolve(
+        &self,
+        active_app: Option<&str>,
+        key: &str,
+        code: &str,
+        modifiers: KeyModifiers,
+    ) -> Option<KeyAction> {
+        active_app
+            .into_iter()
+            .chain([GLOBAL_SCOPE])
+            .find_map(|scope| {
+                self.scopes.get(scope)?.iter().find_map(|(action, combos)| {
+                    combos
+                        .iter()
+                        .any(|c| c.matches(key, code, modifiers))
+                        .then(|| KeyAction {
+                            scope: scope.to_string(),
+                            action: action.clone(),
+                        })
+                })
+            })
+    }
+
+    pub fn combos(&self, scope: &str, action: &str) -> Vec<KeyCombo> {
+        self.scopes
+            .get(scope)
+            .and_then(|keymap| keymap.iter().find(|(a, _)| a == action))
+            .map_or(vec![], |(_, combos)| combos.clone())
+    }
+}
+
+thread_local! {
+    static KEYMAPS: RefCell<Keymaps> = RefCell::new(Keymaps::default());
+}
+
+pub fn register_keymap(scope: &str, defaults: KeymapDefaults) -> Vec<KeyError> {
+    KEYMAPS.with(|keymaps| keymaps.borrow_mut().register(scope, defaults))
+}
+
+/// Applies the `[keybindings]` config, call it after all keymaps are registered.
+pub fn configure_keymaps(
+    bindings: &HashMap<String, HashMap<String, Vec<String>>>,
+) -> Vec<KeyError> {
+    KEYMAPS.with(|keymaps| keymaps.borrow_mut().configure(bindings))
+}
+
+/// Key combos of an action, e.g. for tooltips.
+pub fn key_combos(scope: &str, action: &str) -> Vec<KeyCombo> {
+    KEYMAPS.with(|keymaps| keymaps.borrow().combos(scope, action))
+}
+
+/// Publishes the action bound to the key, if any, and tells whether there was one.
+pub fn handle_key_event(event: &KeyboardEvent) -> bool {
+    let modifiers = event.modifiers();
+    let modifiers = KeyModifiers {
+        ctrl: modifiers.ctrl(),
+        alt: modifiers.alt(),
+        shift: modifiers.shift(),
+        meta: modifiers.meta(),
+    };
+    let active_app = ACTIVE_APP.peek().clone();
+    let action = KEYMAPS.with(|keymaps| {
+        keymaps.borrow().resolve(
+            active_app.as_deref(),
+            &event.key().to_string(),
+            &event.code().to_string(),
+            modifiers,
+        )
+    });
+
+    match action {
+        Some(action) => {
+            publish(action);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Calls the handler with the actions of the scope, for the lifetime of the component.
+pub fn use_key_action(scope: &'static str, mut handler: impl FnMut(&str) + 'static) {
+    use_event(move |action: KeyAction| {
+        if action.scope == scope {
+            handler(&action.action);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALT: KeyModifiers = KeyModifiers {
+        ctrl: false,
+        alt: true,
+        shift: false,
+        meta: false,
+    };
+
+    #[test]
+    fn test_parse_and_display() {
+        let combo = KeyCombo::parse("ctrl + shift+k").unwrap();
+        assert!(combo.modifiers.ctrl && combo.modifiers.shift);
+        assert_eq!(combo.to_string(), "Ctrl+Shift+K");
+
+        assert!(KeyCombo::parse("Ctrl+").is_err());
+        assert!(KeyCombo::parse("Ctrl+K+J").is_err());
+        assert!(KeyCombo::parse("Alt").is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let combo = KeyCombo::parse("Alt+1").unwrap();
+        assert!(combo.matches("1", "Digit1", ALT));
+        assert!(combo.matches("¡", "Digit1", ALT));
+        assert!(!combo.matches("1", "Digit1", KeyModifiers::default()));
+
+        let combo = KeyCombo::parse("Alt+ArrowDown").unwrap();
+        assert!(combo.matches("ArrowDown", "ArrowDown", ALT));
+    }
+
+    #[test]
+    fn test_app_keymap_goes_before_global() {
+        let mut keymaps = Keymaps::default();
+        keymaps.register(
+            GLOBAL_SCOPE,
+            &[("next_app", "Alt+ArrowDown"), ("app_1", "Alt+1")],
+        );
+        keymaps.register("messenger", &[("next_chat", "Alt+ArrowDown")]);
+
+        let action = keymaps.resolve(Some("messenger"), "ArrowDown", "ArrowDown", ALT);
+        assert_eq!(action.unwrap().action, "next_chat");
+
+        let action = keymaps
+            .resolve(Some("messenger"), "1", "Digit1", ALT)
+            .unwrap();
+        assert_eq!(
+            (action.scope.as_str(), action.action.as_str()),
+            (GLOBAL_SCOPE, "app_1")
+        );
+
+        let action = keymaps.resolve(None, "ArrowDown", "ArrowDown", ALT);
+        assert_eq!(action.unwrap().action, "next_app");
+    }
+
+    #[test]
+    fn test_invalid_defaults_are_left_unbound() {
+        let mut keymaps = Keymaps::default();
+
+        let errors = keymaps.register(
+            "messenger",
+            &[("next_chat", "Alt+ArrowDown"), ("broken", "Ctrl+")],
+        );
+
+        assert_eq!(errors, vec![KeyError::InvalidCombo("Ctrl+".to_string())]);
+        assert!(keymaps.combos("messenger", "broken").is_empty());
+        assert_eq!(keymaps.combos("messenger", "next_chat").len(), 1);
+    }
+
+    #[test]
+    fn test_configure_overrides_defaults() {
+        let mut keymaps = Keymaps::default();
+        keymaps.register("messenger", &[("next_chat", "Alt+ArrowDown")]);
+        let bindings = HashMap::from([(
+            "messenger".to_string(),
+            HashMap::from([
+                ("next_chat".to_string(), vec!["Ctrl+J".to_string()]),
+                ("unknown".to_string(), vec!["Ctrl+U".to_string()]),
+            ]),
+        )]);
+
+        let errors = keymaps.configure(&bindings);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            keymaps.combos("messenger", "next_chat"),
+            vec![KeyCombo::parse("Ctrl+J").unwrap()]
+        );
+        assert!(
+            keymaps
+                .resolve(Some("messenger"), "ArrowDown", "ArrowDown", ALT)
+                .is_none()
+        );
+    }
+}
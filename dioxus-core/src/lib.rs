@@ -1,2 +1,6 @@
+pub mod events;
+pub mod keys;
+pub mod plugin;
+pub mod routing;
 pub mod state;
 pub mod utils;
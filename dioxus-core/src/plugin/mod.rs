@@ -0,0 +1,81 @@
+//! App plugins.
+//!
+//! An app crate describes itself with a [`PluginDeclaration`], usually through [`export_app!`].
+//! The shell collects the declarations statically, see `web/build.rs`, and registers the
+//! enabled ones.
+
+use crate::keys::KeyError;
+use crate::state::app::{SharedApp, register_app};
+use lcore::config::Apps;
+use lcore::prelude::*;
+use std::fmt;
+
+/// Entry point of an app plugin.
+pub struct PluginDeclaration {
+    pub name: &'static str,
+    pub create: fn() -> SharedApp,
+}
+
+/// Declares the app plugin of the current module as `PLUGIN`.
+///
+/// Example:
+/// export_app!(NAME, MessengerApp);
+#[macro_export]
+macro_rules! export_app {
+    ($name:expr, $app:expr) => {
+        pub static PLUGIN: $crate::plugin::PluginDeclaration = $crate::plugin::PluginDeclaration {
+            name: $name,
+            create: || ::std::sync::Arc::new($app),
+        };
+    };
+}
+
+/// Registers every declaration that is enabled in the config, returns the rejected ones and the
+/// key bindings that were skipped.
+pub fn register_plugins<'a>(
+    plugins: impl IntoIterator<Item = &'a PluginDeclaration>,
+    apps: &Apps,
+    storage: SharedStorage,
+) -> Vec<PluginError> {
+    let mut errors = vec![];
+    for plugin in plugins {
+        if !apps.is_app_enabled(plugin.name) {
+            continue;
+        }
+        match register_app((plugin.create)(), storage.clone()) {
+            Ok(key_errors) => {
+                errors.extend(
+                    key_errors
+                        .into_iter()
+                        .map(|error| PluginError::InvalidKeymap {
+                            name: plugin.name.to_string(),
+                            error,
+                        }),
+                )
+            }
+            Err(e) => errors.push(PluginError::InvalidName(e)),
+        }
+    }
+    errors
+}
+
+#[derive(Debug, Clone)]
+pub enum PluginError {
+    InvalidName(InvalidAppName),
+    InvalidKeymap { name: String, error: KeyError },
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PluginError::InvalidName(e) => write!(f, "App can't be registered: {}", e),
+            PluginError::InvalidKeymap { name, error } => {
+                write!(
+                    f,
+                    "App {} has an invalid default key binding: {}",
+                    name, error
+                )
+            }
+        }
+    }
+}
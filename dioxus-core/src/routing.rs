@@ -0,0 +1,89 @@
+//! Navigation between apps and inside them.
+//!
+//! The shell owns the route tree and mounts an app under `/apps/<name>/...`. Everything after
+//! the app name is the app route, which the app parses itself, see `App::accepts_route`.
+
+use dioxus::prelude::*;
+use lcore::utils::encode_path_segment;
+
+pub const APPS_PATH: &str = "/apps";
+pub const LOGIN_PATH: &str = "/login";
+
+/// Route of the active app, without the `/apps/<name>` prefix.
+pub static APP_ROUTE: GlobalSignal<Vec<String>> = Global::new(Vec::new);
+
+/// The segments are percent-encoded, the router decodes them again.
+pub fn app_path(app_name: &str, segments: &[String]) -> String {
+    let mut path = format!("{}/{}", APPS_PATH, app_name);
+    for segment in segments {
+        path.push('/');
+        path.push_str(&encode_path_segment(segment));
+    }
+    path
+}
+
+pub fn navigate_to_app(app_name: &str, segments: &[String]) {
+    navigator().push(app_path(app_name, segments));
+}
+
+pub fn replace_app_route(app_name: &str, segments: &[String]) {
+    navigator().replace(app_path(app_name, segments));
+}
+
+/// Subscribes the calling component to the active app route.
+pub fn use_app_route() -> Vec<String> {
+    APP_ROUTE.read().clone()
+}
+
+/// Called by the shell when the router mounts an app.
+pub fn set_app_route(segments: &[String]) {
+    if APP_ROUTE.peek().as_slice() != segments {
+        *APP_ROUTE.write() = segments.to_vec();
+    }
+}
+
+/// Returns `redirect` when it is a path on this site, `/` otherwise.
+///
+/// Browsers read `//host` and `/\host` as another origin, so only a single leading slash passes.
+pub fn local_redirect(redirect: &str) -> &str {
+    let mut chars = redirect.chars();
+    match (chars.next(), chars.next()) {
+        (Some('/'), Some('/' | '\\')) => "/",
+        (Some('/'), _) => redirect,
+        _ => "/",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_path_encodes_segments() {
+        let segments = ["chats".to_string(), "1".to_string(), "a/b?c".to_string()];
+        assert_eq!(
+            app_path("messenger", &segments),
+            "/apps/messenger/chats/1/a%2Fb%3Fc"
+        );
+    }
+
+    #[test]
+    fn test_local_redirect() {
+        assert_eq!(
+            local_redirect("/apps/messenger/chat/1"),
+            "/apps/messenger/chat/1"
+        );
+        assert_eq!(local_redirect("/"), "/");
+        assert_eq!(local_redirect("/login?redirect=%2F"), "/login?redirect=%2F");
+    }
+
+    #[test]
+    fn test_local_redirect_rejects_other_origins() {
+        assert_eq!(local_redirect(""), "/");
+        assert_eq!(local_redirect("https://evil.example"), "/");
+        assert_eq!(local_redirect("//evil.example"), "/");
+        assert_eq!(local_redirect("/\\evil.example"), "/");
+        assert_eq!(local_redirect("javascript:alert(1)"), "/");
+        assert_eq!(local_redirect("apps/messenger"), "/");
+    }
+}
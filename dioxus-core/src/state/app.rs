@@ -1,44 +1,232 @@
+use crate::events::{AppActivated, AppDeactivated, LoggedIn, LoggedOut, publish};
+use crate::keys::{KeyError, KeymapDefaults, register_keymap};
 use crate::state::types::AppComponent;
 use dioxus::prelude::*;
 use lcore::prelude::*;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 
 const ACTIVE_APP_KEY: &str = "active_app";
 pub static ACTIVE_APP: GlobalSignal<Option<String>> = Global::new(|| None);
 
-static APP_REGISTRY: Lazy<RwLock<HashMap<String, AppComponent>>> =
+/// An application that can be plugged into the shell.
+///
+/// Only the metadata and the main component are required, everything else has a default.
+pub trait App: Send + Sync {
+    /// Unique name, used as the registry key and persisted as the active app.
+    fn name(&self) -> &'static str;
+
+    /// Human-readable title, shown as the launcher tooltip.
+    fn title(&self) -> &'static str;
+
+    fn icon(&self) -> &'static str;
+
+    /// Position in the launcher list, lower goes first.
+    fn order(&self) -> i32 {
+        0
+    }
+
+    fn component(&self) -> AppComponent;
+
+    /// Whether the app can render the given app route, unknown routes show "not found".
+    fn accepts_route(&self, segments: &[String]) -> bool {
+        segments.is_empty()
+    }
+
+    /// Replaces the default icon launcher.
+    fn launcher(&self) -> Option<AppComponent> {
+        None
+    }
+
+    /// Rendered on top of the default launcher, e.g. an unread counter.
+    fn badge(&self) -> Option<AppComponent> {
+        None
+    }
+
+    /// Mounted at the root whichever app is active, e.g. to keep the app's state up to date.
+    fn background(&self) -> Option<AppComponent> {
+        None
+    }
+
+    /// Default key bindings of the app's actions, see [`crate::keys`].
+    fn keymap(&self) -> KeymapDefaults {
+        &[]
+    }
+
+    fn on_register(&self, _ctx: &AppContext) {}
+
+    fn on_activate(&self, _ctx: &AppContext) {}
+
+    fn on_deactivate(&self, _ctx: &AppContext) {}
+
+    fn on_login(&self, _ctx: &AppContext) {}
+
+    fn on_logout(&self, _ctx: &AppContext) {}
+}
+
+pub type SharedApp = Arc<dyn App>;
+
+/// What an app gets from the shell, passed to the lifecycle hooks and provided to the app
+/// components as context.
+#[derive(Clone)]
+pub struct AppContext {
+    pub name: &'static str,
+    pub storage: ScopedStorage,
+}
+
+impl AppContext {
+    pub fn new(name: &'static str, storage: &SharedStorage) -> Result<Self, InvalidAppName> {
+        Ok(Self {
+            name,
+            storage: storage.scoped(name)?,
+        })
+    }
+}
+
+struct RegisteredApp {
+    app: SharedApp,
+    context: AppContext,
+}
+
+static APP_REGISTRY: Lazy<RwLock<HashMap<String, RegisteredApp>>> =
     Lazy::new(|| RwLock::new(HashMap::new()));
 
-pub fn register_app(name: &str, app: AppComponent) {
-    APP_REGISTRY.write().unwrap().insert(name.to_string(), app);
+/// Apps only get their own [`ScopedStorage`], never the storage of the shell.
+///
+/// Returns the default key bindings of the app that were skipped because they are invalid.
+pub fn register_app(
+    app: SharedApp,
+    storage: SharedStorage,
+) -> Result<Vec<KeyError>, InvalidAppName> {
+    let context = AppContext::new(app.name(), &storage)?;
+    APP_REGISTRY.write().unwrap().insert(
+        app.name().to_string(),
+        RegisteredApp {
+            app: app.clone(),
+            context: context.clone(),
+        },
+    );
+    let key_errors = register_keymap(app.name(), app.keymap());
+    app.on_register(&context);
+    Ok(key_errors)
 }
 
 pub fn remove_app(name: &str) {
     APP_REGISTRY.write().unwrap().remove(name);
 }
 
-pub fn get_app(name: &str) -> Option<AppComponent> {
-    APP_REGISTRY.read().unwrap().get(name).copied()
+pub fn get_app(name: &str) -> Option<SharedApp> {
+    APP_REGISTRY
+        .read()
+        .unwrap()
+        .get(name)
+        .map(|r| r.app.clone())
+}
+
+pub fn get_app_context(name: &str) -> Option<AppContext> {
+    APP_REGISTRY
+        .read()
+        .unwrap()
+        .get(name)
+        .map(|r| r.context.clone())
+}
+
+/// All registered apps in launcher order.
+pub fn get_apps() -> Vec<SharedApp> {
+    get_apps_with_context()
+        .into_iter()
+        .map(|(app, _)| app)
+        .collect()
+}
+
+fn get_apps_with_context() -> Vec<(SharedApp, AppContext)> {
+    let mut apps: Vec<(SharedApp, AppContext)> = APP_REGISTRY
+        .read()
+        .unwrap()
+        .values()
+        .map(|r| (r.app.clone(), r.context.clone()))
+        .collect();
+    apps.sort_by(|(a, _), (b, _)| a.order().cmp(&b.order()).then(a.name().cmp(b.name())));
+    apps
+}
+
+fn with_app(name: &str, f: impl FnOnce(&dyn App, &AppContext)) {
+    let registered = APP_REGISTRY
+        .read()
+        .unwrap()
+        .get(name)
+        .map(|r| (r.app.clone(), r.context.clone()));
+    if let Some((app, context)) = registered {
+        f(app.as_ref(), &context);
+    }
+}
+
+/// Removes everything the app has stored, without touching other apps.
+pub fn clear_app_data(name: &str) {
+    if let Some(context) = get_app_context(name) {
+        context.storage.clear();
+    }
+}
+
+/// Context of the app whose component is being rendered.
+pub fn use_app_context() -> AppContext {
+    use_context::<AppContext>()
 }
 
 pub fn set_active_app(app_name: &str, storage: SharedStorage) {
+    let previous = ACTIVE_APP.peek().clone();
+    if previous.as_deref() == Some(app_name) {
+        return;
+    }
+    if let Some(previous) = previous.as_deref() {
+        with_app(previous, |app, ctx| app.on_deactivate(ctx));
+        publish(AppDeactivated {
+            name: previous.to_string(),
+        });
+    }
+
     storage.set(ACTIVE_APP_KEY, app_name);
     *ACTIVE_APP.write() = Some(app_name.to_string());
+
+    with_app(app_name, |app, ctx| app.on_activate(ctx));
+    publish(AppActivated {
+        name: app_name.to_string(),
+    });
 }
 
 pub fn clear_active_app(storage: SharedStorage) {
+    let previous = ACTIVE_APP.peek().clone();
+    if let Some(previous) = previous {
+        with_app(&previous, |app, ctx| app.on_deactivate(ctx));
+        publish(AppDeactivated { name: previous });
+    }
     storage.remove(ACTIVE_APP_KEY);
     *ACTIVE_APP.write() = None;
 }
 
-pub fn get_active_app() -> Option<AppComponent> {
+pub fn get_active_app() -> Option<SharedApp> {
     ACTIVE_APP.read().as_ref().and_then(|name| get_app(name))
 }
 
 pub fn load_active_app(storage: SharedStorage) {
     if let Some(app_name) = storage.get(ACTIVE_APP_KEY) {
-        *ACTIVE_APP.write() = Some(app_name);
+        with_app(&app_name, |app, ctx| app.on_activate(ctx));
+        *ACTIVE_APP.write() = Some(app_name.clone());
+        publish(AppActivated { name: app_name });
+    }
+}
+
+pub(crate) fn notify_login() {
+    for (app, context) in get_apps_with_context() {
+        app.on_login(&context);
+    }
+    publish(LoggedIn);
+}
+
+pub(crate) fn notify_logout() {
+    for (app, context) in get_apps_with_context() {
+        app.on_logout(&context);
     }
+    publish(LoggedOut);
 }
@@ -1,3 +1,4 @@
+use crate::state::app::{notify_login, notify_logout};
 use dioxus::prelude::*;
 use lcore::traits::AuthState;
 use std::sync::{Arc, RwLock};
@@ -13,6 +14,12 @@ impl SharedAuthState {
     }
 }
 
+impl Default for SharedAuthState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AuthState for SharedAuthState {
     fn set_authenticated(&self) {
         self.0.write().unwrap().set_authenticated();
@@ -31,11 +38,19 @@ pub struct DioxusAuthState;
 
 impl DioxusAuthState {
     fn set_authenticated(&self) {
+        if *IS_AUTHENTICATED.peek() {
+            return;
+        }
         *IS_AUTHENTICATED.write() = true;
+        notify_login();
     }
 
     fn set_not_authenticated(&self) {
+        if !*IS_AUTHENTICATED.peek() {
+            return;
+        }
         *IS_AUTHENTICATED.write() = false;
+        notify_logout();
     }
 
     fn is_authenticated(&self) -> bool {
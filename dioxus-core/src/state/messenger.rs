@@ -0,0 +1,220 @@
+use dioxus::prelude::*;
+use lcore::api::schemas::{ChatFolder, MessageModel, Presence, User};
+use lcore::helpers::types::{ChatId, UserId};
+use lcore::messenger::models::Chat;
+use lcore::messenger::store::{Changes, MessengerStore};
+use std::collections::HashMap;
+
+/// `MessengerStore` exposed as signals.
+///
+/// Every chat and every conversation gets its own signal, created on first use, and an update
+/// only writes the signals of what it has changed. A new message re-renders its chat item and
+/// its conversation, but not the rest of the list.
+#[derive(Clone, Copy, PartialEq)]
+pub struct MessengerSignals {
+    scope: ScopeId,
+    store: CopyValue<MessengerStore>,
+    chat_ids: Signal<Vec<ChatId>>,
+    chats: CopyValue<HashMap<ChatId, Signal<Option<Chat>>>>,
+    messages: CopyValue<HashMap<ChatId, Signal<Vec<MessageModel>>>>,
+    users: Signal<HashMap<UserId, User>>,
+    presence: Signal<HashMap<UserId, Presence>>,
+    typing: CopyValue<HashMap<ChatId, Signal<Vec<UserId>>>>,
+    read_watermarks: CopyValue<HashMap<ChatId, Signal<HashMap<UserId, u64>>>>,
+    folders: Signal<Vec<ChatFolder>>,
+    me: Signal<Option<UserId>>,
+    unread: Signal<u32>,
+}
+
+impl MessengerSignals {
+    fn new(scope: ScopeId) -> Self {
+        Self {
+            scope,
+            store: CopyValue::new_in_scope(MessengerStore::default(), scope),
+            chat_ids: Signal::new_in_scope(vec![], scope),
+            chats: CopyValue::new_in_scope(HashMap::new(), scope),
+            messages: CopyValue::new_in_scope(HashMap::new(), scope),
+            users: Signal::new_in_scope(HashMap::new(), scope),
+            presence: Signal::new_in_scope(HashMap::new(), scope),
+            typing: CopyValue::new_in_scope(HashMap::new(), scope),
+            read_watermarks: CopyValue::new_in_scope(HashMap::new(), scope),
+            folders: Signal::new_in_scope(vec![], scope),
+            me: Signal::new_in_scope(None, scope),
+            unread: Signal::new_in_scope(0, scope),
+        }
+    }
+
+    /// Chat ids, most recent first. Changes only when a chat is added or moves.
+    pub fn chat_ids(&self) -> Signal<Vec<ChatId>> {
+        self.chat_ids
+    }
+
+    pub fn chat(&self, chat_id: ChatId) -> Signal<Option<Chat>> {
+        let store = self.store;
+        let scope = self.scope;
+        *self
+            .chats
+            .write_unchecked()
+            .entry(chat_id)
+            .or_insert_with(|| Signal::new_in_scope(store.read().chat(chat_id).cloned(), scope))
+    }
+
+    pub fn messages(&self, chat_id: ChatId) -> Signal<Vec<MessageModel>> {
+        let store = self.store;
+        let scope = self.scope;
+        *self
+            .messages
+            .write_unchecked()
+            .entry(chat_id)
+            .or_insert_with(|| Signal::new_in_scope(store.read().messages(chat_id).to_vec(), scope))
+    }
+
+    pub fn users(&self) -> Signal<HashMap<UserId, User>> {
+        self.users
+    }
+
+    pub fn presence(&self) -> Signal<HashMap<UserId, Presence>> {
+        self.presence
+    }
+
+    /// Other members typing in the chat.
+    pub fn typing(&self, chat_id: ChatId) -> Signal<Vec<UserId>> {
+        let store = self.store;
+        let scope = self.scope;
+        *self
+            .typing
+            .write_unchecked()
+            .entry(chat_id)
+            .or_insert_with(|| Signal::new_in_scope(store.read().typing_users(chat_id), scope))
+    }
+
+    /// Seq of the last message each member has read.
+    pub fn read_watermarks(&self, chat_id: ChatId) -> Signal<HashMap<UserId, u64>> {
+        let store = self.store;
+        let scope = self.scope;
+        *self
+            .read_watermarks
+            .write_unchecked()
+            .entry(chat_id)
+            .or_insert_with(|| Signal::new_in_scope(store.read().read_watermarks(chat_id), scope))
+    }
+
+    pub fn folders(&self) -> Signal<Vec<ChatFolder>> {
+        self.folders
+    }
+
+    pub fn me(&self) -> Signal<Option<UserId>> {
+        self.me
+    }
+
+    /// Unread messages in all chats.
+    pub fn unread(&self) -> Signal<u32> {
+        self.unread
+    }
+
+    pub fn set_me(&mut self, me: Option<UserId>) {
+        self.store.write().me = me.clone();
+        self.me.set(me);
+    }
+
+    /// Same as `MessengerStore::chat_title`, but also re-renders when usernames arrive.
+    pub fn chat_title(&self, chat: &Chat) -> String {
+        let _users = self.users.read();
+        self.store.read().chat_title(chat)
+    }
+
+    pub fn username(&self, user_id: &UserId) -> String {
+        self.users
+            .read()
+            .get(user_id)
+            .map_or("Unknown".to_string(), |u| u.username.clone())
+    }
+
+    /// Forgets everything, e.g. after logging out, the signals in use are emptied.
+    pub fn clear(&mut self) {
+        *self.store.write() = MessengerStore::default();
+        for mut signal in self.chats.peek().values().copied() {
+            signal.set(None);
+        }
+        for mut signal in self.messages.peek().values().copied() {
+            signal.set(vec![]);
+        }
+        for mut signal in self.typing.peek().values().copied() {
+            signal.set(vec![]);
+        }
+        for mut signal in self.read_watermarks.peek().values().copied() {
+            signal.set(HashMap::new());
+        }
+        self.chat_ids.set(vec![]);
+        self.users.set(HashMap::new());
+        self.presence.set(HashMap::new());
+        self.folders.set(vec![]);
+        self.me.set(None);
+        self.unread.set(0);
+    }
+
+    /// Reads the store without subscribing to anything.
+    pub fn peek<R>(&self, f: impl FnOnce(&MessengerStore) -> R) -> R {
+        f(&self.store.read())
+    }
+
+    /// Updates the store and writes the signals of what has changed.
+    pub fn apply(&mut self, f: impl FnOnce(&mut MessengerStore) -> Changes) -> Changes {
+        let changes = f(&mut self.store.write());
+        self.sync(&changes);
+        changes
+    }
+
+    fn sync(&mut self, changes: &Changes) {
+        let store = self.store.read();
+
+        for chat_id in &changes.chats {
+            if let Some(mut signal) = self.chats.peek().get(chat_id).copied() {
+                signal.set(store.chat(*chat_id).cloned());
+            }
+        }
+        for chat_id in &changes.messages {
+            if let Some(mut signal) = self.messages.peek().get(chat_id).copied() {
+                signal.set(store.messages(*chat_id).to_vec());
+            }
+        }
+        if changes.order {
+            self.chat_ids.set(store.chat_ids());
+        }
+        // messages, read state and chat settings all change the counts, and the total is
+        // cheap compared to finding out which change can affect it
+        let unread = store.total_unread();
+        if *self.unread.peek() != unread {
+            self.unread.set(unread);
+        }
+        if changes.users {
+            self.users.set(store.users().clone());
+        }
+        if changes.presence {
+            self.presence.set(store.presences().clone());
+        }
+        for chat_id in &changes.typing {
+            if let Some(mut signal) = self.typing.peek().get(chat_id).copied() {
+                signal.set(store.typing_users(*chat_id));
+            }
+        }
+        if changes.folders {
+            self.folders.set(store.folders().to_vec());
+        }
+        for chat_id in &changes.read {
+            if let Some(mut signal) = self.read_watermarks.peek().get(chat_id).copied() {
+                signal.set(store.read_watermarks(*chat_id));
+            }
+        }
+    }
+}
+
+/// Creates the signals at the root on first use and provides them to every component, so that
+/// they outlive the messenger's own components.
+pub fn use_messenger_provider() -> MessengerSignals {
+    use_root_context(|| MessengerSignals::new(ScopeId::ROOT))
+}
+
+pub fn use_messenger() -> MessengerSignals {
+    use_context::<MessengerSignals>()
+}
@@ -1,5 +1,8 @@
 use std::collections::HashMap;
+use std::time::Duration;
+use dioxus::html::FileData;
 use dioxus::prelude::FormValue;
+use lcore::api::schemas::FileUpload;
 
 pub fn form_values_to_string(values: &[(String, FormValue)]) -> HashMap<String, String> {
     values
@@ -10,3 +13,22 @@ pub fn form_values_to_string(values: &[(String, FormValue)]) -> HashMap<String,
         })
         .collect()
 }
+
+/// Reads a picked or dropped file into memory for uploading.
+pub async fn read_file(file: &FileData) -> Result<FileUpload, String> {
+    let data = file.read_bytes().await.map_err(|e| e.to_string())?;
+    let mime = file
+        .content_type()
+        .filter(|mime| !mime.is_empty())
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+
+    Ok(FileUpload::new(file.name(), mime, data.to_vec()))
+}
+
+/// Waits without blocking the UI thread, on the browser as well as on native.
+pub async fn sleep(duration: Duration) {
+    #[cfg(target_arch = "wasm32")]
+    gloo_timers::future::sleep(duration).await;
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::time::sleep(duration).await;
+}
@@ -1,16 +1,59 @@
-use crate::messenger::MessengerApp;
+use crate::home::use_shell_storage;
+use dcore::keys::{GLOBAL_SCOPE, KeymapDefaults, key_combos, use_key_action};
+use dcore::routing::navigate_to_app;
+use dcore::state::app::{ACTIVE_APP, get_app, get_apps};
 use dcore::state::auth::SharedAuthState;
 use dioxus::prelude::*;
 use lcore::api::client::SharedApiClient;
 use lcore::api::schemas::AuthError;
 use lcore::auth;
-use lcore::prelude::*;
 use manganis::asset;
 
 const CSS: Asset = asset!("/assets/styling/apps.css");
 
+/// Global shortcuts, registered under `GLOBAL_SCOPE` by the platform crates.
+pub const SHELL_KEYMAP: KeymapDefaults = &[
+    ("app_1", "Alt+1"),
+    ("app_2", "Alt+2"),
+    ("app_3", "Alt+3"),
+    ("app_4", "Alt+4"),
+    ("app_5", "Alt+5"),
+    ("app_6", "Alt+6"),
+    ("app_7", "Alt+7"),
+    ("app_8", "Alt+8"),
+    ("app_9", "Alt+9"),
+    ("next_app", "Alt+PageDown"),
+    ("previous_app", "Alt+PageUp"),
+];
+
+/// Switches to the app of a `SHELL_KEYMAP` action, in launcher order.
+fn switch_app(action: &str) {
+    let apps = get_apps();
+    if apps.is_empty() {
+        return;
+    }
+    let active = ACTIVE_APP
+        .peek()
+        .as_deref()
+        .and_then(|name| apps.iter().position(|app| app.name() == name));
+    let index = match action {
+        "next_app" => active.map_or(0, |i| (i + 1) % apps.len()),
+        "previous_app" => active.map_or(0, |i| (i + apps.len() - 1) % apps.len()),
+        _ => match action
+            .strip_prefix("app_")
+            .and_then(|n| n.parse::<usize>().ok())
+        {
+            Some(n) if (1..=apps.len()).contains(&n) => n - 1,
+            _ => return,
+        },
+    };
+    navigate_to_app(apps[index].name(), &[]);
+}
+
 #[component]
 pub fn AppsView() -> Element {
+    use_key_action(GLOBAL_SCOPE, switch_app);
+
     rsx! {
         document::Link { rel: "stylesheet", href: CSS }
 
@@ -19,8 +62,15 @@ pub fn AppsView() -> Element {
 
             div {
                 class: "apps-list",
-                // hardcoded for now, will load dynamically later
-                MessengerApp {}
+                for (i, app) in get_apps().into_iter().enumerate() {
+                    AppLauncher {
+                        key: "{app.name()}",
+                        name: app.name(),
+                        shortcut: key_combos(GLOBAL_SCOPE, &format!("app_{}", i + 1))
+                            .first()
+                            .map(ToString::to_string),
+                    }
+                }
             }
 
             div {
@@ -30,12 +80,45 @@ pub fn AppsView() -> Element {
         }
     }
 }
+
+/// The `shortcut` of the `SHELL_KEYMAP` action that switches to the app goes into the tooltip.
+#[component]
+fn AppLauncher(name: String, shortcut: Option<String>) -> Element {
+    let Some(app) = get_app(&name) else {
+        return rsx! {};
+    };
+    if let Some(Launcher) = app.launcher() {
+        return rsx! { Launcher {} };
+    }
+    let is_active = ACTIVE_APP.read().as_deref() == Some(app.name());
+    let title = match shortcut {
+        Some(shortcut) => format!("{} ({})", app.title(), shortcut),
+        None => app.title().to_string(),
+    };
+
+    rsx! {
+        div {
+            class: "app-launcher",
+            title,
+
+            div {
+                class: if is_active { "app-icon active" } else { "app-icon" },
+                onclick: move |_| navigate_to_app(&name, &[]),
+                "{app.icon()}"
+            }
+            if let Some(Badge) = app.badge() {
+                Badge {}
+            }
+        }
+    }
+}
+
 #[component]
 pub fn Menu() -> Element {
     let auth_state = use_context::<SharedAuthState>();
     let client = use_context::<SharedApiClient>();
     let error = use_signal::<Option<String>>(|| None);
-    let storage = use_context::<SharedStorage>();
+    let storage = use_shell_storage();
 
     let mut show_menu = use_signal(|| false);
 
@@ -1,6 +1,8 @@
 use crate::apps::AppsView;
-use crate::login;
-use dcore::state::app::get_active_app;
+use crate::routes::{Redirect, Route};
+use dcore::keys::handle_key_event;
+use dcore::routing::{app_path, set_app_route};
+use dcore::state::app::{ACTIVE_APP, get_app, get_app_context, get_apps, set_active_app};
 use dcore::state::auth::SharedAuthState;
 use dioxus::prelude::*;
 use lcore::prelude::*;
@@ -9,44 +11,137 @@ use manganis::asset;
 const CSS: Asset = asset!("/assets/styling/home.css");
 const GENERIC_CSS: Asset = asset!("/assets/styling/generic.css");
 
+/// Storage of the shell, out of reach of the apps, which get their own scoped storage through
+/// `AppContext`.
+#[derive(Clone)]
+struct ShellStorage(SharedStorage);
+
+/// Provides the storage to the shell, called by the platform before rendering [`App`].
+pub fn provide_shell_storage(storage: SharedStorage) {
+    use_context_provider(|| ShellStorage(storage));
+}
+
+pub(crate) fn use_shell_storage() -> SharedStorage {
+    use_context::<ShellStorage>().0
+}
+
 #[component]
 pub fn App() -> Element {
-    let auth_state = use_context::<SharedAuthState>();
-
     rsx! {
         document::Link { rel: "stylesheet", href: CSS }
         document::Link { rel: "stylesheet", href: GENERIC_CSS }
 
+        for app in get_apps().into_iter().filter(|app| app.background().is_some()) {
+            AppBackground { key: "{app.name()}", name: app.name() }
+        }
+        Router::<Route> {}
+    }
+}
+
+/// Layout of every authenticated page, unauthenticated users are sent to the login page.
+#[component]
+pub fn Shell() -> Element {
+    let auth_state = use_context::<SharedAuthState>();
+    let route = use_route::<Route>();
+
+    if !auth_state.is_authenticated() {
+        return rsx! {
+            Redirect { to: Route::LoginPage { redirect: route.to_string() }.to_string() }
+        };
+    }
+
+    rsx! {
         div {
             class: "app-container",
-            {
-                if auth_state.is_authenticated() {
-                    rsx! {
-                        AppView {}
-                        AppsView {}
-                    }
-                } else {
-                    rsx! {
-                        login::Login {}
-                    }
+            // focusable, so shortcuts also work when nothing else has the focus
+            tabindex: "-1",
+            onkeydown: move |evt| {
+                if handle_key_event(&evt) {
+                    evt.prevent_default();
                 }
-            }
+            },
+            Outlet::<Route> {}
+            AppsView {}
         }
     }
 }
 
 #[component]
-fn AppView() -> Element {
-    if let Some(app) = get_active_app() {
-        let App = app;
+pub fn Home() -> Element {
+    let last_app = ACTIVE_APP
+        .peek()
+        .clone()
+        .filter(|name| get_app(name).is_some());
+    if let Some(name) = last_app {
         return rsx! {
-            App {}
+            Redirect { to: app_path(&name, &[]) }
         };
     }
 
     rsx! { Empty {} }
 }
 
+#[component]
+pub fn AppPage(app: String, segments: Vec<String>) -> Element {
+    let storage = use_shell_storage();
+    let accepted = get_app(&app).map(|registered| registered.accepts_route(&segments));
+
+    use_effect(use_reactive!(|app, segments, accepted| {
+        if accepted == Some(true) {
+            set_app_route(&segments);
+            set_active_app(&app, storage.clone());
+        }
+    }));
+
+    match accepted {
+        None => return rsx! { Empty {} },
+        Some(false) => {
+            return rsx! {
+                Redirect { to: app_path(&app, &[]) }
+            };
+        }
+        Some(true) => {}
+    }
+
+    rsx! {
+        AppHost { key: "{app}", name: app }
+    }
+}
+
+/// Renders a registered app with its context, keyed by name so every app gets its own.
+#[component]
+fn AppHost(name: String) -> Element {
+    use_context_provider(|| get_app_context(&name).expect("App is not registered"));
+
+    let Some(app) = get_app(&name) else {
+        return rsx! { Empty {} };
+    };
+    let App = app.component();
+    rsx! {
+        App {}
+    }
+}
+
+/// Renders the background component of a registered app with its context.
+#[component]
+fn AppBackground(name: String) -> Element {
+    use_context_provider(|| get_app_context(&name).expect("App is not registered"));
+
+    let Some(Background) = get_app(&name).and_then(|app| app.background()) else {
+        return rsx! {};
+    };
+    rsx! {
+        Background {}
+    }
+}
+
+#[component]
+pub fn NotFound(segments: Vec<String>) -> Element {
+    rsx! {
+        Redirect { to: "/" }
+    }
+}
+
 #[component]
 fn Empty() -> Element {
     rsx! {
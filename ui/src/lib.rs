@@ -2,4 +2,6 @@ pub mod apps;
 pub mod generic;
 pub mod home;
 pub mod login;
+#[cfg(feature = "messenger")]
 pub mod messenger;
+pub mod routes;
@@ -1,3 +1,6 @@
+use crate::home::use_shell_storage;
+use crate::routes::{Redirect, Route};
+use dcore::routing::local_redirect;
 use dcore::state::auth::SharedAuthState;
 use dcore::utils::form_values_to_string;
 use dioxus::core_macro::{component, rsx};
@@ -13,6 +16,29 @@ use validator::Validate;
 
 const CSS: Asset = asset!("/assets/styling/login.css");
 
+/// Sends the user back to `redirect` once authenticated, if it is a route of this site.
+#[component]
+pub fn LoginPage(redirect: String) -> Element {
+    let auth_state = use_context::<SharedAuthState>();
+
+    if auth_state.is_authenticated() {
+        let to = match redirect.parse::<Route>() {
+            Ok(_) => local_redirect(&redirect).to_string(),
+            Err(_) => "/".to_string(),
+        };
+        return rsx! {
+            Redirect { to }
+        };
+    }
+
+    rsx! {
+        div {
+            class: "app-container",
+            Login {}
+        }
+    }
+}
+
 #[component]
 pub fn Login() -> Element {
     let mut active_tab = use_signal(|| "login".to_string());
@@ -60,9 +86,9 @@ pub fn Login() -> Element {
 pub fn LoginForm() -> Element {
     let auth_state = use_context::<SharedAuthState>();
     let client = use_context::<SharedApiClient>();
-    let storage = use_context::<SharedStorage>();
+    let storage = use_shell_storage();
 
-    let mut error = use_signal(|| String::new());
+    let mut error = use_signal(String::new);
     let mut processing = use_signal(|| false);
 
     rsx! {
@@ -150,10 +176,10 @@ pub fn LoginForm() -> Element {
 pub fn RegisterForm() -> Element {
     let auth_state = use_context::<SharedAuthState>();
     let client = use_context::<SharedApiClient>();
-    let storage = use_context::<SharedStorage>();
+    let storage = use_shell_storage();
 
-    let mut error_password = use_signal(|| String::new());
-    let mut error_username = use_signal(|| String::new());
+    let mut error_password = use_signal(String::new);
+    let mut error_username = use_signal(String::new);
     let mut processing = use_signal(|| false);
 
     rsx! {
@@ -175,19 +201,21 @@ pub fn RegisterForm() -> Element {
                 };
 
                 if let Err(validation_errors) = req.validate() {
-                    if let Some(errs) = validation_errors.field_errors().get("username") {
-                        if let Some(e) = errs.first() {
-                            if let Some(m) = &e.message {
-                                error_username.set(m.to_string());
-                            }
-                        }
+                    if let Some(m) = validation_errors
+                        .field_errors()
+                        .get("username")
+                        .and_then(|errs| errs.first())
+                        .and_then(|e| e.message.as_ref())
+                    {
+                        error_username.set(m.to_string());
                     }
-                    if let Some(errs) = validation_errors.field_errors().get("password") {
-                        if let Some(e) = errs.first() {
-                            if let Some(m) = &e.message {
-                                error_password.set(m.to_string());
-                            }
-                        }
+                    if let Some(m) = validation_errors
+                        .field_errors()
+                        .get("password")
+                        .and_then(|errs| errs.first())
+                        .and_then(|e| e.message.as_ref())
+                    {
+                        error_password.set(m.to_string());
                     }
                     processing.set(false);
                     return;
@@ -1,212 +1,293 @@
-use crate::generic::ShortBorder;
+use crate::messenger::{
+    ChatDraft, ChatList, ChatTabs, ComposeChat, JoinChat, JoinWithCode, MessageSearchResults,
+    MessengerConversationArea, MessengerRoute, NewGroupBar, QuickSwitcher, SearchBar,
+    UserSearchResults, use_own_presence,
+};
+use dcore::events::{LoggedIn, LoggedOut, use_event};
+use dcore::keys::{KeymapDefaults, use_key_action};
+use dcore::routing::{APP_ROUTE, navigate_to_app, use_app_route};
+use dcore::state::app::App;
+use dcore::state::auth::SharedAuthState;
+use dcore::state::messenger::{MessengerSignals, use_messenger_provider};
+use dcore::state::types::AppComponent;
+use dcore::utils::sleep;
 use dioxus::core_macro::{component, rsx};
 use dioxus::dioxus_core::Element;
-use dioxus::hooks::use_signal;
 use dioxus::prelude::*;
-use dcore::state::app::set_active_app;
+use lcore::api::client::{ApiError, SharedApiClient};
+use lcore::api::schemas::{ServerCapabilities, WsEvent};
+use lcore::helpers::types::ChatId;
+use lcore::messenger::models::ChatFilter;
+use lcore::messenger::store::{Changes, MessengerStore};
 use lcore::prelude::*;
+use lcore::utils::now_timestamp;
 use manganis::asset;
+use std::time::Duration;
 
-const CSS: Asset = asset!("/assets/styling/messenger/main.css");
+pub(crate) const CSS: Asset = asset!("/assets/styling/messenger/main.css");
 pub const NAME: &str = "messenger";
 
-#[component]
-pub fn MessengerApp() -> Element {
-    let storage = use_context::<SharedStorage>();
+pub struct MessengerApp;
 
-    rsx! {
-        div {
-            class: "app-icon",
-            onclick: move |_| set_active_app(NAME, storage.clone()),
-            "💬"
-        }
+impl App for MessengerApp {
+    fn name(&self) -> &'static str {
+        NAME
     }
-}
 
-#[component]
-pub fn Messenger() -> Element {
-    let selected_chat = use_signal(|| None::<(String, Vec<(String, String)>)>);
+    fn title(&self) -> &'static str {
+        "Messenger"
+    }
 
-    rsx! {
-        document::Link { rel: "stylesheet", href: CSS }
+    fn icon(&self) -> &'static str {
+        "💬"
+    }
 
-        div {
-            class: "messenger-container",
-            Sidebar { selected_chat: selected_chat }
-            MessengerConversationArea { selected_chat: selected_chat }
-        }
+    fn component(&self) -> AppComponent {
+        Messenger
     }
-}
 
-#[component]
-pub fn MessengerConversationArea(
-    selected_chat: Signal<Option<(String, Vec<(String, String)>)>>,
-) -> Element {
-    rsx! {
-        document::Link { rel: "stylesheet", href: CSS }
+    fn accepts_route(&self, segments: &[String]) -> bool {
+        MessengerRoute::parse(segments).is_some()
+    }
 
-        div {
-            class: "conversation-area",
-            {
-                match &*selected_chat.read() {
-                    Some((title, messages)) => rsx! {
-                        Chat {
-                            title: title.clone(),
-                            messages: messages.clone(),
-                            on_send: move |msg: String| {
-                                println!("Sending message: {}", msg);
-                            }
-                        }
-                    },
-                    None => rsx! {
-                        div {
-                            class: "no-chat-selected",
-                            "Select a chat to start messaging!"
-                        }
-                    }
-                }
-            }
-        }
+    fn badge(&self) -> Option<AppComponent> {
+        Some(UnreadBadge)
+    }
+
+    fn background(&self) -> Option<AppComponent> {
+        Some(MessengerSync)
+    }
+
+    fn keymap(&self) -> KeymapDefaults {
+        KEYMAP
     }
 }
 
+const KEYMAP: KeymapDefaults = &[
+    ("next_chat", "Alt+ArrowDown"),
+    ("previous_chat", "Alt+ArrowUp"),
+    // Ctrl+F stays the browser's find
+    ("focus_search", "Ctrl+Shift+F"),
+    ("focus_input", "Alt+M"),
+    ("quick_switch", "Ctrl+K"),
+];
+
+fn focus(selector: &str) {
+    document::eval(&format!("document.querySelector('{}')?.focus()", selector));
+}
+
+/// Shared by all messenger components.
+#[derive(Clone, Copy, PartialEq)]
+pub struct MessengerState {
+    pub store: MessengerSignals,
+    pub error: Signal<Option<String>>,
+    pub draft: Signal<Option<ChatDraft>>,
+    pub capabilities: Signal<ServerCapabilities>,
+}
+
+/// Created at the root like the store, the state outlives the messenger while another app is
+/// active.
+fn use_messenger_state() -> MessengerState {
+    let store = use_messenger_provider();
+    use_root_context(|| MessengerState {
+        store,
+        error: Signal::new_in_scope(None, ScopeId::ROOT),
+        draft: Signal::new_in_scope(None, ScopeId::ROOT),
+        capabilities: Signal::new_in_scope(ServerCapabilities::default(), ScopeId::ROOT),
+    })
+}
+
+/// Loads the chats and keeps them up to date with the socket events, mounted at the root so it
+/// runs while another app is active.
 #[component]
-pub fn Sidebar(selected_chat: Signal<Option<(String, Vec<(String, String)>)>>) -> Element {
-    rsx! {
-        div { class: "sidebar",
-            SearchBar {}
-            ChatList { selected_chat: selected_chat }
+fn MessengerSync() -> Element {
+    let mut state = use_messenger_state();
+    let client = use_context::<SharedApiClient>();
+    let reload = move |client: SharedApiClient| {
+        let mut state = state;
+        spawn(async move {
+            if let Err(e) = load_chats(client, state).await {
+                state.error.set(Some(e.to_string()));
+            }
+        });
+    };
+    let client_on_mount = client.clone();
+    // mounted at the root, so also on the login page
+    let authenticated = use_context::<SharedAuthState>().is_authenticated();
+    use_hook(move || {
+        if authenticated {
+            reload(client_on_mount)
         }
-    }
+    });
+    let client_on_login = client.clone();
+    use_event(move |_: LoggedIn| reload(client_on_login.clone()));
+    // the next user mustn't see the chats of the previous one
+    use_event(move |_: LoggedOut| {
+        state.store.clear();
+        state.error.set(None);
+        state.draft.set(None);
+    });
+    use_event(move |event: WsEvent| {
+        let changes = state.store.apply(|store| store.apply_event(event));
+        if !changes.chats.is_empty() {
+            let client = client.clone();
+            spawn(async move {
+                if let Err(e) = load_users(&client, state).await {
+                    state.error.set(Some(e.to_string()));
+                }
+            });
+        }
+    });
+
+    use_own_presence();
+    // typing indicators go away when the typing events stop coming
+    use_future(move || async move {
+        loop {
+            sleep(Duration::from_secs(1)).await;
+            state
+                .store
+                .apply(|store| store.expire_typing(now_timestamp()));
+        }
+    });
+
+    rsx! {}
 }
 
+/// Unread messages on the launcher.
 #[component]
-pub fn SearchBar() -> Element {
+fn UnreadBadge() -> Element {
+    let state = use_messenger_state();
+    let unread = state.store.unread()();
+
     rsx! {
-        div { class: "search-bar",
-            input {
-                "type": "text",
-                placeholder: "Search"
+        if unread > 0 {
+            div {
+                class: "app-badge",
+                if unread > 99 { "99+" } else { "{unread}" }
             }
         }
     }
 }
 
-#[component]
-pub fn ChatList(selected_chat: Signal<Option<(String, Vec<(String, String)>)>>) -> Element {
-    let chats = vec![
-        (
-            "John Doe".to_string(),
-            vec![("John".to_string(), "Hello!".to_string())],
-        ),
-        (
-            "Jane Smith".to_string(),
-            vec![("Jane".to_string(), "Hi there!".to_string())],
-        ),
-    ];
+async fn load_chats(client: SharedApiClient, mut state: MessengerState) -> Result<(), ApiError> {
+    state.store.set_me(client.user_id().await);
+    // servers without the endpoint get the default limits
+    if let Ok(capabilities) = client.get_capabilities().await {
+        state.capabilities.set(capabilities);
+    }
+    let chats = client.get_chats().await?.chats;
+    state.store.apply(|store| store.apply_chats(chats));
+    // without folders on the server, the chat list only has its fixed tabs
+    if let Ok(result) = client.get_folders().await {
+        state
+            .store
+            .apply(|store| store.apply_folders(result.folders));
+    }
+    load_users(&client, state).await
+}
 
-    rsx! {
-        document::Link { rel: "stylesheet", href: CSS }
-        div { class: "chat-list",
-            {chats.into_iter().map(|(title, messages)| {
-                let title_clone = title.clone();
-                let messages_clone = messages.clone();
-                rsx! {
-                    ChatItem {
-                        key: "{title}",
-                        title: title_clone,
-                        preview: messages_clone.first().map_or("".to_string(), |(_, content)| content.clone()),
-                        time: "14:32".to_string(),
-                        on_click: move |_| selected_chat.set(Some((title.clone(), messages.clone())))
-                    }
-                    ShortBorder {}
-                }
-            })}
-        }
+/// Fetches the members that aren't known yet.
+pub(crate) async fn load_users(
+    client: &SharedApiClient,
+    mut state: MessengerState,
+) -> Result<(), ApiError> {
+    let missing = state.store.peek(MessengerStore::missing_user_ids);
+    if missing.is_empty() {
+        return Ok(());
     }
+
+    let users = client.get_users_by_ids(missing).await?.users;
+    state.store.apply(|store| store.apply_users(users));
+    Ok(())
 }
 
 #[component]
-pub fn ChatItem(
-    title: String,
-    preview: String,
-    time: String,
-    on_click: EventHandler<()>,
-) -> Element {
-    rsx! {
-        div {
-            class: "chat-item",
-            onclick: move |_| on_click.call(()),
-            div { class: "chat-info",
-                div { class: "chat-title",
-                    "{title}"
-                }
-                div { class: "chat-preview",
-                    "{preview}"
-                }
-            }
-            div { class: "chat-time",
-                "{time}"
+pub fn Messenger() -> Element {
+    let route = MessengerRoute::parse(&use_app_route()).unwrap_or(MessengerRoute::Chats);
+    let mut state = use_messenger_state();
+
+    let mut switcher_open = use_signal(|| false);
+    use_key_action(NAME, move |action| match action {
+        "next_chat" | "previous_chat" => {
+            // the handler outlives renders, so read the route when it's called
+            let selected_chat_id =
+                MessengerRoute::parse(&APP_ROUTE.peek()).and_then(|r| r.chat_id());
+            let mut chat_id = None;
+            state.store.apply(|store| {
+                store.select_chat(selected_chat_id);
+                chat_id = if action == "next_chat" {
+                    store.select_next()
+                } else {
+                    store.select_previous()
+                };
+                Changes::default()
+            });
+            if let Some(chat_id) = chat_id {
+                let route = MessengerRoute::Chat {
+                    chat_id,
+                    message_id: None,
+                };
+                navigate_to_app(NAME, &route.segments());
             }
         }
-    }
-}
+        "focus_search" => focus(".search-bar input"),
+        "focus_input" => focus(".message-input"),
+        "quick_switch" => switcher_open.set(true),
+        _ => {}
+    });
 
-#[component]
-pub fn Chat(
-    title: String,
-    messages: Vec<(String, String)>,
-    on_send: EventHandler<String>,
-) -> Element {
     rsx! {
-        div { class: "chat visible",
-            div { class: "chat-header",
-                "{title}"
+        document::Link { rel: "stylesheet", href: CSS }
+
+        div {
+            class: "messenger-container",
+            Sidebar { selected_chat_id: route.chat_id() }
+            if switcher_open() {
+                QuickSwitcher { open: switcher_open }
             }
-            div { class: "chat-messages",
-                {messages.into_iter().map(|(author, content)| {
-                    rsx! {
-                        div {
-                            key: "{author}-{content}",
-                            class: "chat-message",
-                            div { class: "message-author",
-                                "{author}"
-                            }
-                            div { class: "message-content",
-                                "{content}"
-                            }
-                        }
+            match route.clone() {
+                MessengerRoute::NewChat => rsx! {
+                    ComposeChat {}
+                },
+                MessengerRoute::Join { code } => rsx! {
+                    JoinChat { code }
+                },
+                _ => rsx! {
+                    MessengerConversationArea {
+                        selected_chat_id: route.chat_id(),
+                        message_id: route.message_id().map(str::to_string),
                     }
-                })}
-            }
-            MessageInput {
-                on_send: on_send
+                },
             }
         }
     }
 }
 
 #[component]
-pub fn MessageInput(on_send: EventHandler<String>) -> Element {
-    let mut input_value = use_signal(String::new);
+pub fn Sidebar(selected_chat_id: Option<ChatId>) -> Element {
+    let state = use_context::<MessengerState>();
+    let query = use_signal(String::new);
+    let selected = use_signal(Vec::new);
+    let filter = use_signal(ChatFilter::default);
 
     rsx! {
-        div { class: "message-input-container",
-            input {
-                class: "message-input",
-                value: "{input_value.read()}",
-                placeholder: "Type your message...",
-                oninput: move |evt| input_value.set(evt.value().clone())
+        div { class: "sidebar",
+            SearchBar { query: query }
+            JoinWithCode {}
+            if let Some(error) = state.error.read().as_ref() {
+                div { class: "messenger-error",
+                    "{error}"
+                }
             }
-            button {
-                class: "message-send-button",
-                onclick: move |_| {
-                    if !input_value.read().is_empty() {
-                        on_send.call(input_value.read().clone());
-                        input_value.set(String::new());
-                    }
-                },
-                "Send"
+            if !selected.read().is_empty() {
+                NewGroupBar { query: query, selected: selected }
+            }
+            if query.read().trim().is_empty() {
+                ChatTabs { filter }
+                ChatList { selected_chat_id: selected_chat_id, filter }
+            } else {
+                UserSearchResults { query: query, selected: selected }
+                MessageSearchResults { query: query }
             }
         }
     }
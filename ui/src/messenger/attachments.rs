@@ -0,0 +1,255 @@
+use crate::messenger::MessengerState;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use dcore::utils::{read_file, sleep};
+use dioxus::html::FileData;
+use dioxus::prelude::*;
+use lcore::api::client::SharedApiClient;
+use lcore::api::schemas::{Attachment, FileUpload, UploadProgress};
+use lcore::helpers::types::ChatId;
+use lcore::media::prepare_upload;
+use lcore::utils::format_file_size;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// A file being attached to the next message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Upload {
+    pub id: String,
+    pub name: String,
+    pub progress: UploadProgress,
+    pub attachment: Option<Attachment>,
+    pub error: Option<String>,
+}
+
+impl Upload {
+    pub fn is_running(&self) -> bool {
+        self.attachment.is_none() && self.error.is_none()
+    }
+}
+
+/// Reads the files and uploads them one after the other.
+pub async fn upload_files(
+    client: SharedApiClient,
+    state: MessengerState,
+    chat_id: ChatId,
+    mut uploads: Signal<Vec<Upload>>,
+    files: Vec<FileData>,
+) {
+    for file in files {
+        match read_file(&file).await {
+            Ok(file) => upload_file(client.clone(), state, chat_id, uploads, file).await,
+            Err(e) => {
+                let mut upload = new_upload(file.name(), file.size());
+                upload.error = Some(e);
+                uploads.push(upload);
+            }
+        }
+    }
+}
+
+/// Downscales images and strips their metadata before uploading, see [`prepare_upload`].
+pub async fn upload_file(
+    client: SharedApiClient,
+    state: MessengerState,
+    chat_id: ChatId,
+    mut uploads: Signal<Vec<Upload>>,
+    file: FileUpload,
+) {
+    let upload = new_upload(file.name.clone(), file.data.len() as u64);
+    let id = upload.id.clone();
+    uploads.push(upload);
+
+    // the upload may have been removed in the meantime
+    let mut update = move |f: &dyn Fn(&mut Upload)| {
+        if let Some(upload) = uploads.write().iter_mut().find(|u| u.id == id) {
+            f(upload);
+        }
+    };
+    // the image processing blocks the thread, let the upload show up first
+    sleep(Duration::ZERO).await;
+    let file = match prepare_upload(file, &state.capabilities.peek()) {
+        Ok(file) => file,
+        Err(e) => {
+            update(&|upload| upload.error = Some(e.to_string()));
+            return;
+        }
+    };
+    let total = file.data.len() as u64;
+    update(&|upload| upload.progress.total = total);
+    let result = client
+        .upload_attachment(chat_id, file, |progress| {
+            update(&|upload| upload.progress = progress)
+        })
+        .await;
+    match result {
+        Ok(attachment) => update(&|upload| upload.attachment = Some(attachment.clone())),
+        Err(e) => update(&|upload| upload.error = Some(e.to_string())),
+    }
+}
+
+fn new_upload(name: String, size: u64) -> Upload {
+    Upload {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        progress: UploadProgress {
+            sent: 0,
+            total: size,
+        },
+        attachment: None,
+        error: None,
+    }
+}
+
+#[derive(Deserialize)]
+struct PastedFile {
+    name: String,
+    mime: String,
+    data: String,
+}
+
+// forwards files pasted inside the element, base64 encoded, the handler is kept on the window so
+// that it can be removed
+const PASTE_JS: &str = r#"
+    window.pasteHandlers ??= {};
+    if (window.pasteHandlers['{id}']) document.removeEventListener('paste', window.pasteHandlers['{id}']);
+    window.pasteHandlers['{id}'] = event => {
+        if (!event.target.closest?.('#{id}')) return;
+        const files = [...(event.clipboardData?.files ?? [])];
+        if (files.length === 0) return;
+        event.preventDefault();
+        for (const file of files) {
+            const reader = new FileReader();
+            reader.onload = () => dioxus.send({
+                name: file.name || 'pasted',
+                mime: file.type,
+                data: reader.result.split(',')[1] ?? '',
+            });
+            reader.readAsDataURL(file);
+        }
+    };
+    document.addEventListener('paste', window.pasteHandlers['{id}']);
+    await new Promise(() => {});
+"#;
+
+const REMOVE_PASTE_JS: &str = r#"
+    document.removeEventListener('paste', window.pasteHandlers?.['{id}']);
+    delete window.pasteHandlers?.['{id}'];
+"#;
+
+/// Calls the handler with the files pasted inside the element, e.g. screenshots.
+pub fn use_pasted_files(element_id: &'static str, mut handler: impl FnMut(FileUpload) + 'static) {
+    use_hook(move || {
+        spawn(async move {
+            let mut eval = document::eval(&PASTE_JS.replace("{id}", element_id));
+            while let Ok(file) = eval.recv::<PastedFile>().await {
+                let Ok(data) = STANDARD.decode(file.data) else {
+                    continue;
+                };
+                handler(FileUpload::new(file.name, file.mime, data));
+            }
+        });
+    });
+    use_drop(move || {
+        document::eval(&REMOVE_PASTE_JS.replace("{id}", element_id));
+    });
+}
+
+#[component]
+pub fn AttachButton(on_files: EventHandler<Vec<FileData>>) -> Element {
+    rsx! {
+        label { class: "attach-button", title: "Attach files",
+            "📎"
+            input {
+                "type": "file",
+                multiple: true,
+                onchange: move |evt| on_files.call(evt.files()),
+            }
+        }
+    }
+}
+
+/// The attachments of the next message with their upload progress.
+#[component]
+pub fn UploadList(uploads: Signal<Vec<Upload>>) -> Element {
+    rsx! {
+        div { class: "upload-list",
+            for upload in uploads.read().iter().cloned() {
+                div {
+                    key: "{upload.id}",
+                    class: if upload.error.is_some() { "upload failed" } else { "upload" },
+                    title: upload.error.clone().unwrap_or_default(),
+                    if let Some(attachment) = upload.attachment.as_ref().filter(|a| a.is_image()) {
+                        img { class: "upload-preview", src: "{attachment.preview_url(64)}" }
+                    }
+                    span { class: "upload-name", "{upload.name}" }
+                    if upload.is_running() {
+                        progress {
+                            class: "upload-progress",
+                            value: "{upload.progress.fraction()}",
+                        }
+                    }
+                    button {
+                        class: "upload-remove",
+                        title: "Remove",
+                        onclick: move |_| uploads.write().retain(|u| u.id != upload.id),
+                        "✕"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn AttachmentView(attachment: Attachment) -> Element {
+    let size = format_file_size(attachment.size);
+
+    if attachment.is_image() {
+        // the dimensions keep the messages from jumping while the image loads
+        let ratio = match (attachment.width, attachment.height) {
+            (Some(width), Some(height)) if height > 0 => format!("{} / {}", width, height),
+            _ => "auto".to_string(),
+        };
+        return rsx! {
+            a {
+                class: "attachment-image",
+                href: "{attachment.url}",
+                target: "_blank",
+                title: "{attachment.name} ({size})",
+                img {
+                    src: "{attachment.preview_url(480)}",
+                    alt: "{attachment.name}",
+                    style: "aspect-ratio: {ratio}",
+                }
+            }
+        };
+    }
+
+    rsx! {
+        a {
+            class: "attachment-file",
+            href: "{attachment.url}",
+            download: "{attachment.name}",
+            span { class: "attachment-icon", "📄" }
+            span { class: "attachment-name", "{attachment.name}" }
+            span { class: "attachment-size", "{size}" }
+        }
+    }
+}
+
+/// Keeps a dropped file from being opened by the browser and uploads it instead.
+pub fn drop_files(
+    evt: DragEvent,
+    client: SharedApiClient,
+    state: MessengerState,
+    chat_id: ChatId,
+    uploads: Signal<Vec<Upload>>,
+) {
+    evt.prevent_default();
+    let files = evt.data_transfer().files();
+    if files.is_empty() {
+        return;
+    }
+    spawn(upload_files(client, state, chat_id, uploads, files));
+}
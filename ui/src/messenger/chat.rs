@@ -0,0 +1,849 @@
+use crate::messenger::{
+    AttachButton, AttachmentView, CSS, GroupSettings, MessengerState, SeenBy, SystemMessage,
+    TypingIndicator, Upload, UploadList, drop_files, load_users, seen_positions, upload_file,
+    upload_files, use_pasted_files,
+};
+use dcore::events::publish;
+use dioxus::prelude::*;
+use lcore::api::client::{ApiResult, SharedApiClient};
+use lcore::api::schemas::{
+    Attachment, DeleteScope, DeliveryStatus, MessageModel, MessageQuote, NewMessage,
+    ReactionUpdate, WsCommand,
+};
+use lcore::helpers::types::{ChatId, MessageId, UserId};
+use lcore::messenger::typing::TypingThrottle;
+use lcore::utils::{format_timestamp, now_timestamp};
+
+#[component]
+pub fn MessengerConversationArea(
+    selected_chat_id: Option<ChatId>,
+    message_id: Option<MessageId>,
+) -> Element {
+    let client = use_context::<SharedApiClient>();
+    let mut state = use_context::<MessengerState>();
+
+    // the chat list only has the latest messages, load the whole chat when it's opened
+    let loader_client = client.clone();
+    use_effect(use_reactive!(|selected_chat_id| {
+        let Some(chat_id) = selected_chat_id else {
+            return;
+        };
+        let client = loader_client.clone();
+        spawn(async move {
+            let result = match client.get_chat(chat_id).await {
+                Ok(chat) => {
+                    state.store.apply(|store| store.apply_chat(chat));
+                    load_users(&client, state).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                state.error.set(Some(e.to_string()));
+            }
+        });
+    }));
+
+    let selected_chat = selected_chat_id.and_then(|id| state.store.chat(id).read().clone());
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: CSS }
+
+        div {
+            class: "conversation-area",
+            {
+                match selected_chat {
+                    Some(chat) => rsx! {
+                        Chat {
+                            title: state.store.chat_title(&chat),
+                            chat_id: chat.id,
+                            message_id: message_id,
+                            on_send: move |outgoing: OutgoingMessage| {
+                                let client = client.clone();
+                                spawn(async move {
+                                    let Some(sender_id) = client.user_id().await else {
+                                        return;
+                                    };
+                                    let message = NewMessage::new(chat.id, sender_id, outgoing.text)
+                                        .with_reply_to(outgoing.reply_to)
+                                        .with_attachments(outgoing.attachments);
+                                    send_message(client, state, message).await;
+                                });
+                            }
+                        }
+                    },
+                    None => rsx! {
+                        div {
+                            class: "no-chat-selected",
+                            "Select a chat to start messaging!"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// What the composer of a chat sends.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutgoingMessage {
+    pub text: String,
+    pub reply_to: Option<MessageQuote>,
+    pub attachments: Vec<Attachment>,
+}
+
+/// Shows the message as pending right away, then as sent or failed.
+pub(crate) async fn send_message(
+    client: SharedApiClient,
+    mut state: MessengerState,
+    message: NewMessage,
+) {
+    let (chat_id, client_id) = (message.chat_id, message.client_id.clone());
+    let pending = message.to_pending(now_timestamp());
+    state.store.apply(|store| store.apply_message(pending));
+
+    match client.send_message(message).await {
+        Ok(message) => {
+            state.store.apply(|store| store.apply_message(message));
+        }
+        Err(e) => {
+            state
+                .store
+                .apply(|store| store.mark_failed(chat_id, &client_id));
+            state.error.set(Some(e.to_string()));
+        }
+    }
+}
+
+const HISTORY_PAGE_SIZE: u32 = 50;
+
+/// Loads the page of history before the oldest loaded message.
+pub(crate) async fn load_older_messages(
+    client: &SharedApiClient,
+    mut state: MessengerState,
+    chat_id: ChatId,
+) -> ApiResult<()> {
+    let before = state.store.peek(|store| store.oldest_seq(chat_id));
+    let page = client
+        .get_messages(chat_id, before, HISTORY_PAGE_SIZE)
+        .await?;
+    state
+        .store
+        .apply(|store| store.apply_history(chat_id, page));
+    Ok(())
+}
+
+/// Scrolls to a message, e.g. a quoted one or a search result, loading older history until
+/// it's there.
+pub(crate) async fn show_message(
+    client: SharedApiClient,
+    mut state: MessengerState,
+    chat_id: ChatId,
+    message_id: MessageId,
+    seq: u64,
+) {
+    loop {
+        let (oldest, has_older) = state
+            .store
+            .peek(|store| (store.oldest_seq(chat_id), store.has_older_messages(chat_id)));
+        if !has_older || oldest.is_none_or(|oldest| oldest <= seq) {
+            break;
+        }
+        if let Err(e) = load_older_messages(&client, state, chat_id).await {
+            state.error.set(Some(e.to_string()));
+            return;
+        }
+        // the server has nothing older after all
+        if state.store.peek(|store| store.oldest_seq(chat_id)) == oldest {
+            break;
+        }
+    }
+    let _ = document::eval(SHOW_MESSAGE_JS).send(message_id);
+}
+
+// waits for the loaded history to render, then scrolls to the message and flashes it
+const SHOW_MESSAGE_JS: &str = r#"
+    const id = await dioxus.recv();
+    await new Promise(resolve => requestAnimationFrame(resolve));
+    const message = document.getElementById('message-' + id);
+    if (!message) return;
+    message.scrollIntoView({ block: 'center' });
+    message.classList.add('highlighted');
+    setTimeout(() => message.classList.remove('highlighted'), 1500);
+"#;
+
+const SCROLL_TO_MESSAGE_JS: &str = r#"
+    const id = await dioxus.recv();
+    document.getElementById('message-' + id)?.scrollIntoView({ block: 'center' });
+"#;
+
+// scrolls a freshly opened chat to its first unread message, or to the bottom if there is none
+const SCROLL_TO_UNREAD_JS: &str = r#"
+    await new Promise(resolve => requestAnimationFrame(resolve));
+    const list = document.getElementById('chat-messages');
+    const divider = document.getElementById('new-messages');
+    if (!list) return false;
+    if (divider) divider.scrollIntoView({ block: 'start' });
+    else list.scrollTop = list.scrollHeight;
+    return list.scrollHeight - list.scrollTop - list.clientHeight < 2;
+"#;
+
+#[component]
+pub fn Chat(
+    title: String,
+    chat_id: ChatId,
+    message_id: Option<MessageId>,
+    on_send: EventHandler<OutgoingMessage>,
+) -> Element {
+    let client = use_context::<SharedApiClient>();
+    let mut state = use_context::<MessengerState>();
+    let me = state.store.me().read().clone();
+    let messages = state.store.messages(chat_id).read().clone();
+    let unread_count = state
+        .store
+        .chat(chat_id)
+        .read()
+        .as_ref()
+        .map_or(0, |c| c.unread_count);
+
+    let has_older = state.store.peek(|store| store.has_older_messages(chat_id));
+    let is_group = state
+        .store
+        .chat(chat_id)
+        .read()
+        .as_ref()
+        .is_some_and(|c| c.is_group());
+
+    let mut at_bottom = use_signal(|| false);
+    let mut replying_to = use_signal(|| None::<MessageQuote>);
+    let mut uploads = use_signal(Vec::<Upload>::new);
+    let mut show_settings = use_signal(|| false);
+    // the paste listener lives as long as the component, not the chat
+    let mut current_chat_id = use_signal(|| chat_id);
+
+    let paste_client = client.clone();
+    use_pasted_files("chat", move |file| {
+        let client = paste_client.clone();
+        spawn(upload_file(
+            client,
+            state,
+            *current_chat_id.peek(),
+            uploads,
+            file,
+        ));
+    });
+    // the divider stays where it was when the chat was opened, even after it's marked as read
+    let mut unread_since = use_signal(|| None::<u64>);
+    let scroll_to_unread = move || {
+        spawn(async move {
+            let result = document::eval(SCROLL_TO_UNREAD_JS).join::<bool>().await;
+            at_bottom.set(result.unwrap_or(false));
+        });
+    };
+
+    use_effect(use_reactive(
+        (&chat_id, &message_id),
+        move |(chat_id, message_id)| {
+            unread_since.set(None);
+            at_bottom.set(false);
+            replying_to.set(None);
+            uploads.set(vec![]);
+            show_settings.set(false);
+            current_chat_id.set(chat_id);
+            if message_id.is_none() {
+                scroll_to_unread();
+            }
+        },
+    ));
+    // the messages of a chat opened from the list arrive after it's rendered
+    use_effect(use_reactive!(|chat_id, message_id| {
+        let messages = state.store.messages(chat_id);
+        let _messages = messages.read();
+        let first_unread = state.store.peek(|store| {
+            store
+                .first_unread(chat_id)
+                .map(|i| store.messages(chat_id)[i].seq)
+        });
+        if unread_since.peek().is_none() && first_unread.is_some() {
+            unread_since.set(first_unread);
+            if message_id.is_none() {
+                scroll_to_unread();
+            }
+        }
+    }));
+    use_effect(use_reactive!(|message_id| {
+        // the id comes from the URL, so it's sent as data instead of being part of the script
+        if let Some(message_id) = message_id {
+            let _ = document::eval(SCROLL_TO_MESSAGE_JS).send(message_id);
+        }
+    }));
+    let read_client = client.clone();
+    use_effect(use_reactive!(|chat_id, unread_count| {
+        if !at_bottom() || unread_count == 0 {
+            return;
+        }
+        let client = read_client.clone();
+        spawn(async move {
+            match client.mark_chat_as_read(chat_id).await {
+                Ok(()) => {
+                    state.store.apply(|store| store.mark_read(chat_id));
+                }
+                Err(e) => state.error.set(Some(e.to_string())),
+            }
+        });
+    }));
+
+    let seen_by = seen_positions(
+        &messages,
+        &state.store.read_watermarks(chat_id).read(),
+        me.as_ref(),
+    );
+
+    let divider = unread_since().and_then(|since| {
+        messages
+            .iter()
+            .position(|m| m.seq >= since && me.as_ref() != Some(&m.sender_id))
+    });
+
+    let attachments: Vec<Attachment> = uploads
+        .read()
+        .iter()
+        .filter_map(|u| u.attachment.clone())
+        .collect();
+    let uploading = uploads.read().iter().any(Upload::is_running);
+    let drop_client = client.clone();
+    let attach_client = client.clone();
+
+    rsx! {
+        div {
+            id: "chat",
+            class: "chat visible",
+            ondragover: move |evt| evt.prevent_default(),
+            ondrop: move |evt| drop_files(evt, drop_client.clone(), state, chat_id, uploads),
+            div { class: "chat-header",
+                div { class: "chat-header-title",
+                    "{title}"
+                    TypingIndicator { chat_id }
+                }
+                if is_group {
+                    button {
+                        class: "group-settings-button",
+                        title: "Group info",
+                        onclick: move |_| show_settings.toggle(),
+                        "ⓘ"
+                    }
+                }
+                if unread_count > 0 && divider.is_some() {
+                    button {
+                        class: "jump-to-unread",
+                        onclick: move |_| {
+                            document::eval(
+                                "document.getElementById('new-messages')?.scrollIntoView({ block: 'start' })",
+                            );
+                        },
+                        "{unread_count} unread"
+                    }
+                }
+            }
+            if show_settings() {
+                GroupSettings { chat_id, on_close: move |_| show_settings.set(false) }
+            }
+            div {
+                id: "chat-messages",
+                class: "chat-messages",
+                onscroll: move |evt| {
+                    let bottom = evt.scroll_height() as f64 - evt.scroll_top() - evt.client_height() as f64;
+                    at_bottom.set(bottom < 2.0);
+                },
+                if has_older {
+                    button {
+                        class: "load-older-messages",
+                        onclick: move |_| {
+                            let client = client.clone();
+                            spawn(async move {
+                                if let Err(e) = load_older_messages(&client, state, chat_id).await {
+                                    state.error.set(Some(e.to_string()));
+                                }
+                            });
+                        },
+                        "Load older messages"
+                    }
+                }
+                {messages.into_iter().enumerate().map(|(i, message)| {
+                    let is_mine = me.as_ref() == Some(&message.sender_id);
+                    // the id changes when a pending message is sent, the idempotency key doesn't
+                    let key = message.client_id.clone().unwrap_or(message.id.clone());
+                    rsx! {
+                        Fragment {
+                            key: "{key}",
+                            if divider == Some(i) {
+                                div {
+                                    id: "new-messages",
+                                    class: "new-messages-divider",
+                                    "New messages"
+                                }
+                            }
+                            if message.system.is_some() {
+                                SystemMessage { message }
+                            } else {
+                                ChatMessage {
+                                    seen_by: seen_by.get(&message.id).cloned().unwrap_or_default(),
+                                    message,
+                                    is_mine,
+                                    on_reply: move |quote| replying_to.set(Some(quote)),
+                                }
+                            }
+                        }
+                    }
+                })}
+            }
+            if let Some(quote) = replying_to() {
+                div { class: "reply-composer",
+                    MessageQuoteView { quote }
+                    button {
+                        class: "reply-cancel",
+                        title: "Cancel reply",
+                        onclick: move |_| replying_to.set(None),
+                        "✕"
+                    }
+                }
+            }
+            if !uploads.read().is_empty() {
+                UploadList { uploads }
+            }
+            div { class: "composer",
+                AttachButton {
+                    on_files: move |files| {
+                        let client = attach_client.clone();
+                        spawn(upload_files(client, state, chat_id, uploads, files));
+                    },
+                }
+                MessageInput {
+                    allow_empty: !attachments.is_empty(),
+                    busy: uploading,
+                    on_typing: move |typing| publish(WsCommand::Typing { chat_id, typing }),
+                    on_send: move |text| {
+                        uploads.set(vec![]);
+                        on_send.call(OutgoingMessage {
+                            text,
+                            reply_to: replying_to.take(),
+                            attachments: attachments.clone(),
+                        });
+                    },
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn ChatMessage(
+    message: MessageModel,
+    is_mine: bool,
+    on_reply: EventHandler<MessageQuote>,
+    #[props(default)] seen_by: Vec<UserId>,
+) -> Element {
+    let client = use_context::<SharedApiClient>();
+    let mut state = use_context::<MessengerState>();
+    let mut editing = use_signal(|| None::<String>);
+    let mut deleting = use_signal(|| false);
+
+    let (chat_id, message_id) = (message.chat_id, message.id.clone());
+    let is_sent = !matches!(
+        message.status,
+        DeliveryStatus::Pending | DeliveryStatus::Failed
+    );
+    let history = message
+        .edits
+        .iter()
+        .map(|e| format!("{}: {}", format_timestamp(e.edited_at), e.text))
+        .collect::<Vec<_>>()
+        .join("\n");
+    // the original as it is now, it may have been edited or deleted since the reply
+    let quote = message.reply_to.clone().map(|quote| {
+        let messages = state.store.messages(chat_id);
+        let messages = messages.read();
+        match messages.iter().find(|m| m.id == quote.id) {
+            Some(original) => (original.to_quote(), original.deleted),
+            None => (quote, false),
+        }
+    });
+    let reply_quote = message.to_quote();
+
+    let quote_client = client.clone();
+    let save_client = client.clone();
+    let save_id = message_id.clone();
+    let mut save = move || {
+        let Some(text) = editing.read().as_ref().map(|t| t.trim().to_string()) else {
+            return;
+        };
+        editing.set(None);
+        if text.is_empty() {
+            return;
+        }
+        let (client, message_id) = (save_client.clone(), save_id.clone());
+        spawn(async move {
+            match client.edit_message(chat_id, &message_id, text).await {
+                Ok(message) => {
+                    state.store.apply(|store| store.apply_message(message));
+                }
+                Err(e) => state.error.set(Some(e.to_string())),
+            }
+        });
+    };
+    let delete = move |scope: DeleteScope| {
+        deleting.set(false);
+        let (client, message_id) = (client.clone(), message_id.clone());
+        spawn(async move {
+            match client.delete_message(chat_id, &message_id, scope).await {
+                Ok(()) if scope == DeleteScope::Me => {
+                    state
+                        .store
+                        .apply(|store| store.hide_message(chat_id, &message_id));
+                }
+                // the socket sends the same tombstone, which is then a no-op
+                Ok(()) => {
+                    state
+                        .store
+                        .apply(|store| store.delete_message(chat_id, &message_id));
+                }
+                Err(e) => state.error.set(Some(e.to_string())),
+            }
+        });
+    };
+    let mut delete_for_me = delete.clone();
+    let mut delete_for_everyone = delete;
+
+    if message.deleted {
+        return rsx! {
+            div {
+                id: "message-{message.id}",
+                class: if is_mine { "chat-message mine deleted" } else { "chat-message deleted" },
+                div { class: "message-content", "Message deleted" }
+            }
+        };
+    }
+
+    rsx! {
+        div {
+            id: "message-{message.id}",
+            class: if is_mine { "chat-message mine" } else { "chat-message" },
+            div { class: "message-author",
+                "{state.store.username(&message.sender_id)}"
+            }
+            if let Some((quote, deleted)) = quote {
+                MessageQuoteView {
+                    quote: quote.clone(),
+                    deleted,
+                    onclick: move |_| {
+                        let client = quote_client.clone();
+                        let (id, seq) = (quote.id.clone(), quote.seq);
+                        spawn(show_message(client, state, chat_id, id, seq));
+                    },
+                }
+            }
+            if let Some(text) = editing() {
+                input {
+                    class: "message-input message-edit",
+                    value: "{text}",
+                    onmounted: move |evt| async move {
+                        let _ = evt.set_focus(true).await;
+                    },
+                    oninput: move |evt| editing.set(Some(evt.value())),
+                    onkeydown: move |evt| match evt.key() {
+                        Key::Enter => save(),
+                        Key::Escape => editing.set(None),
+                        _ => {}
+                    },
+                }
+            } else if !message.text.is_empty() {
+                div { class: "message-content",
+                    "{message.text}"
+                }
+            }
+            if !message.attachments.is_empty() {
+                div { class: "message-attachments",
+                    for attachment in message.attachments.iter().cloned() {
+                        AttachmentView { key: "{attachment.id}", attachment }
+                    }
+                }
+            }
+            div { class: "message-time",
+                "{format_timestamp(message.created_at)}"
+                if message.edited_at.is_some() {
+                    span { class: "message-edited", title: "{history}", "edited" }
+                }
+                if is_mine {
+                    MessageStatus { message: message.clone() }
+                }
+            }
+            if is_sent {
+                ReactionBar { message: message.clone() }
+            }
+            if !seen_by.is_empty() {
+                SeenBy { message: message.clone(), user_ids: seen_by }
+            }
+            if is_sent && editing.read().is_none() {
+                div { class: "message-actions",
+                    button {
+                        onclick: move |_| on_reply.call(reply_quote.clone()),
+                        "Reply"
+                    }
+                    if is_mine {
+                        button {
+                            onclick: move |_| editing.set(Some(message.text.clone())),
+                            "Edit"
+                        }
+                    }
+                    if deleting() {
+                        if is_mine {
+                            button {
+                                onclick: move |_| delete_for_everyone(DeleteScope::Everyone),
+                                "Delete for everyone"
+                            }
+                        }
+                        button {
+                            onclick: move |_| delete_for_me(DeleteScope::Me),
+                            "Delete for me"
+                        }
+                        button { onclick: move |_| deleting.set(false), "Cancel" }
+                    } else {
+                        button { onclick: move |_| deleting.set(true), "Delete" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+const REACTION_EMOJIS: &[&str] = &["👍", "❤️", "😂", "🎉", "😮", "😢"];
+
+/// Reaction counts under a message, clicking one toggles the user's own reaction.
+#[component]
+pub fn ReactionBar(message: MessageModel) -> Element {
+    let client = use_context::<SharedApiClient>();
+    let mut state = use_context::<MessengerState>();
+    let me = state.store.me().read().clone();
+    let mut picking = use_signal(|| false);
+
+    let (chat_id, message_id) = (message.chat_id, message.id.clone());
+    let mine = me.clone();
+    let reacted = {
+        let (message, me) = (message.clone(), me.clone());
+        move |emoji: &str| {
+            message
+                .reactions
+                .iter()
+                .any(|r| r.emoji == emoji && me.as_ref().is_some_and(|me| r.user_ids.contains(me)))
+        }
+    };
+    // shown right away, undone if the server refuses it
+    let toggle = move |emoji: String| {
+        picking.set(false);
+        let Some(user_id) = me.clone() else {
+            return;
+        };
+        let update = ReactionUpdate {
+            chat_id,
+            message_id: message_id.clone(),
+            user_id,
+            emoji: emoji.clone(),
+            added: !reacted(&emoji),
+        };
+        state
+            .store
+            .apply(|store| store.apply_reaction(update.clone()));
+        let client = client.clone();
+        spawn(async move {
+            let result = if update.added {
+                client
+                    .add_reaction(chat_id, &update.message_id, emoji)
+                    .await
+            } else {
+                client
+                    .remove_reaction(chat_id, &update.message_id, emoji)
+                    .await
+            };
+            if let Err(e) = result {
+                let undo = ReactionUpdate {
+                    added: !update.added,
+                    ..update
+                };
+                state.store.apply(|store| store.apply_reaction(undo));
+                state.error.set(Some(e.to_string()));
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "reaction-bar",
+            for reaction in message.reactions.iter().cloned() {
+                button {
+                    key: "{reaction.emoji}",
+                    class: if mine.as_ref().is_some_and(|me| reaction.user_ids.contains(me)) { "reaction mine" } else { "reaction" },
+                    title: "{reacted_by(state, &reaction.user_ids, reaction.count)}",
+                    onclick: {
+                        let mut toggle = toggle.clone();
+                        let emoji = reaction.emoji.clone();
+                        move |_| toggle(emoji.clone())
+                    },
+                    "{reaction.emoji} {reaction.count}"
+                }
+            }
+            div { class: "reaction-picker-container",
+                button {
+                    class: "reaction add",
+                    title: "Add reaction",
+                    onclick: move |_| picking.toggle(),
+                    "☺+"
+                }
+                if picking() {
+                    div { class: "reaction-picker",
+                        for emoji in REACTION_EMOJIS {
+                            button {
+                                key: "{emoji}",
+                                onclick: {
+                                    let mut toggle = toggle.clone();
+                                    move |_| toggle(emoji.to_string())
+                                },
+                                "{emoji}"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// the server may only send some of the users of a popular reaction
+fn reacted_by(state: MessengerState, user_ids: &[String], count: u32) -> String {
+    let mut names: Vec<String> = user_ids.iter().map(|id| state.store.username(id)).collect();
+    let others = count as usize - user_ids.len().min(count as usize);
+    if others > 0 {
+        names.push(format!("{} more", others));
+    }
+    names.join(", ")
+}
+
+#[component]
+pub fn MessageQuoteView(
+    quote: MessageQuote,
+    #[props(default)] deleted: bool,
+    onclick: Option<EventHandler<MouseEvent>>,
+) -> Element {
+    let state = use_context::<MessengerState>();
+
+    rsx! {
+        div {
+            class: if onclick.is_some() { "message-quote clickable" } else { "message-quote" },
+            onclick: move |evt| {
+                if let Some(handler) = onclick {
+                    handler.call(evt);
+                }
+            },
+            div { class: "message-quote-author",
+                "{state.store.username(&quote.sender_id)}"
+            }
+            div { class: "message-quote-text",
+                if deleted { "Message deleted" } else { "{quote.text}" }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn MessageStatus(message: MessageModel) -> Element {
+    let client = use_context::<SharedApiClient>();
+    let state = use_context::<MessengerState>();
+
+    let (icon, title) = match message.status {
+        DeliveryStatus::Pending => ("🕓", "Sending"),
+        DeliveryStatus::Sent => ("✓", "Sent"),
+        DeliveryStatus::Delivered => ("✓✓", "Delivered"),
+        DeliveryStatus::Read => ("✓✓", "Read"),
+        DeliveryStatus::Failed => ("⚠", "Not sent"),
+    };
+    let class = match message.status {
+        DeliveryStatus::Read => "message-status read",
+        DeliveryStatus::Failed => "message-status failed",
+        _ => "message-status",
+    };
+
+    rsx! {
+        span { class: class, title: title, "{icon}" }
+        if let Some(retry) = message.to_retry().filter(|_| message.status == DeliveryStatus::Failed) {
+            button {
+                class: "message-retry",
+                onclick: move |_| {
+                    spawn(send_message(client.clone(), state, retry.clone()));
+                },
+                "Retry"
+            }
+        }
+    }
+}
+
+#[component]
+pub fn MessageInput(
+    on_send: EventHandler<String>,
+    /// Sends without text, e.g. when there are attachments.
+    #[props(default)]
+    allow_empty: bool,
+    /// Holds the message back, e.g. while its attachments are uploading.
+    #[props(default)]
+    busy: bool,
+    /// Called with `true` while the user types, throttled, and `false` when they stop.
+    on_typing: Option<EventHandler<bool>>,
+) -> Element {
+    let mut input_value = use_signal(String::new);
+    let mut throttle = use_signal(TypingThrottle::default);
+
+    let mut stop_typing = move || {
+        if throttle.write().on_stop()
+            && let Some(on_typing) = on_typing
+        {
+            on_typing.call(false);
+        }
+    };
+    let mut send = move || {
+        let text = input_value.read().trim().to_string();
+        if !busy && (allow_empty || !text.is_empty()) {
+            on_send.call(text);
+            input_value.set(String::new());
+            stop_typing();
+        }
+    };
+    let oninput = move |evt: FormEvent| {
+        let value = evt.value();
+        if value.trim().is_empty() {
+            stop_typing();
+        } else if throttle.write().on_input(now_timestamp())
+            && let Some(on_typing) = on_typing
+        {
+            on_typing.call(true);
+        }
+        input_value.set(value);
+    };
+
+    rsx! {
+        div { class: "message-input-container",
+            input {
+                class: "message-input",
+                value: "{input_value.read()}",
+                placeholder: "Type your message...",
+                oninput,
+                onkeydown: move |evt| {
+                    if evt.key() == Key::Enter {
+                        send();
+                    }
+                }
+            }
+            button {
+                class: "message-send-button",
+                disabled: busy,
+                onclick: move |_| send(),
+                "Send"
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use crate::generic::ShortBorder;
+use crate::messenger::{CSS, ChatMenu, MessengerRoute, MessengerState, NAME, PresenceDot};
+use dcore::routing::navigate_to_app;
+use dioxus::prelude::*;
+use lcore::helpers::list::ListState;
+use lcore::helpers::types::ChatId;
+use lcore::messenger::models::ChatFilter;
+use lcore::utils::{format_timestamp, now_timestamp};
+
+/// Height of a chat list row in pixels, see `.chat-list-row`. Rows have a fixed height, so
+/// only the ones in view need to be rendered.
+const ROW_HEIGHT: f64 = 89.0;
+// rendered above and below the visible rows, so scrolling doesn't show empty space
+const OVERSCAN: usize = 5;
+
+#[component]
+pub fn ChatList(selected_chat_id: Option<ChatId>, filter: Signal<ChatFilter>) -> Element {
+    let state = use_context::<MessengerState>();
+    let mut viewport = use_signal(ListState::default);
+    // the order, the folders and, for folders of unread chats, the unread count decide what
+    // the tab shows
+    let (order, folders, unread) = (
+        state.store.chat_ids(),
+        state.store.folders(),
+        state.store.unread(),
+    );
+    let _subscriptions = (order.read(), folders.read(), unread.read());
+    let chat_ids = state
+        .store
+        .peek(|store| store.filtered_chat_ids(&filter.read()));
+    let len = chat_ids.len();
+
+    // e.g. switching chats with the keyboard, the selected chat is scrolled into view
+    let selected_index = selected_chat_id.and_then(|id| chat_ids.iter().position(|c| *c == id));
+    use_effect(use_reactive!(|selected_index| {
+        let Some(index) = selected_index else {
+            return;
+        };
+        let offset = viewport.peek().offset();
+        viewport.write().scroll_to(index);
+        let new_offset = viewport.peek().offset();
+        if new_offset != offset {
+            let _ = document::eval(SCROLL_LIST_JS).send(new_offset as f64 * ROW_HEIGHT);
+        }
+    }));
+
+    let visible = viewport.read().visible_range(len);
+    let rendered = if viewport.read().height() == 0 {
+        visible
+    } else {
+        visible.start.saturating_sub(OVERSCAN)..(visible.end + OVERSCAN).min(len)
+    };
+    let space_above = rendered.start as f64 * ROW_HEIGHT;
+    let space_below = (len - rendered.end) as f64 * ROW_HEIGHT;
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: CSS }
+        div {
+            id: "chat-list",
+            class: "chat-list",
+            onmounted: move |evt| async move {
+                if let Ok(rect) = evt.get_client_rect().await {
+                    let rows = (rect.height() / ROW_HEIGHT).ceil() as usize;
+                    viewport.write().set_height(rows, len);
+                }
+            },
+            onscroll: move |evt| {
+                let rows = (evt.client_height() as f64 / ROW_HEIGHT).ceil() as usize;
+                let offset = (evt.scroll_top() / ROW_HEIGHT).floor() as usize;
+                let mut viewport = viewport.write();
+                viewport.set_height(rows, len);
+                viewport.set_offset(offset, len);
+            },
+            if chat_ids.is_empty() {
+                div { class: "no-chats",
+                    if filter() == ChatFilter::All { "No chats yet" } else { "No chats here" }
+                }
+            }
+            div { style: "height: {space_above}px" }
+            for chat_id in chat_ids[rendered].iter().copied() {
+                div { key: "{chat_id}", class: "chat-list-row",
+                    ChatItem {
+                        chat_id: chat_id,
+                        is_selected: selected_chat_id == Some(chat_id),
+                    }
+                    ShortBorder {}
+                }
+            }
+            div { style: "height: {space_below}px" }
+        }
+    }
+}
+
+const SCROLL_LIST_JS: &str = r#"
+    const top = await dioxus.recv();
+    const list = document.getElementById('chat-list');
+    if (list) list.scrollTop = top;
+"#;
+
+/// Subscribed to its own chat only, so other chats' updates don't re-render it.
+#[component]
+pub fn ChatItem(chat_id: ChatId, is_selected: bool) -> Element {
+    let state = use_context::<MessengerState>();
+    let mut menu_open = use_signal(|| false);
+    let chat = state.store.chat(chat_id);
+    let Some(chat) = chat.read().clone() else {
+        return rsx! {};
+    };
+    let is_muted = chat.settings.is_muted(now_timestamp());
+    let title = state.store.chat_title(&chat);
+    let last_message = chat.last_message.as_ref();
+    let preview = last_message.map_or(String::new(), |m| {
+        state
+            .store
+            .peek(|store| store.system_text(m))
+            .unwrap_or(m.text.clone())
+    });
+    let time = last_message.map_or(String::new(), |m| format_timestamp(m.created_at));
+    // presence only makes sense for the other member of a direct chat
+    let me = state.store.me().read().clone();
+    let other_member = match chat.member_ids.as_slice() {
+        [a, b] if me.as_ref() == Some(a) => Some(b.clone()),
+        [a, b] if me.as_ref() == Some(b) => Some(a.clone()),
+        _ => None,
+    };
+
+    rsx! {
+        div {
+            class: if is_selected { "chat-item selected" } else { "chat-item" },
+            onclick: move |_| {
+                let route = MessengerRoute::Chat { chat_id, message_id: None };
+                navigate_to_app(NAME, &route.segments());
+            },
+            div { class: "chat-info",
+                div { class: "chat-title",
+                    if let Some(user_id) = other_member {
+                        PresenceDot { user_id }
+                    }
+                    "{title}"
+                    if chat.settings.pinned {
+                        span { class: "chat-flag", title: "Pinned", "📌" }
+                    }
+                    if is_muted {
+                        span { class: "chat-flag", title: "Muted", "🔕" }
+                    }
+                }
+                div { class: "chat-preview",
+                    "{preview}"
+                }
+            }
+            div { class: "chat-meta",
+                div { class: "chat-time",
+                    "{time}"
+                }
+                if chat.unread_count > 0 {
+                    div { class: if is_muted { "chat-unread muted" } else { "chat-unread" },
+                        "{chat.unread_count}"
+                    }
+                }
+                button {
+                    class: "chat-menu-button",
+                    title: "Chat options",
+                    onclick: move |evt| {
+                        evt.stop_propagation();
+                        menu_open.toggle();
+                    },
+                    "⋯"
+                }
+            }
+            if menu_open() {
+                ChatMenu { chat_id, on_close: move |_| menu_open.set(false) }
+            }
+        }
+    }
+}
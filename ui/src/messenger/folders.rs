@@ -0,0 +1,268 @@
+use crate::messenger::MessengerState;
+use dioxus::prelude::*;
+use lcore::api::client::SharedApiClient;
+use lcore::api::schemas::{ChatFolder, ChatSettings};
+use lcore::helpers::types::ChatId;
+use lcore::messenger::models::ChatFilter;
+use lcore::utils::now_timestamp;
+
+const MUTE_OPTIONS: &[(&str, Option<f64>)] = &[
+    ("1 hour", Some(3600.0)),
+    ("8 hours", Some(8.0 * 3600.0)),
+    ("1 week", Some(7.0 * 86400.0)),
+    ("Until unmuted", None),
+];
+
+/// Applies the change right away and saves it, it's undone if the server refuses it.
+pub fn update_chat_settings(
+    client: SharedApiClient,
+    mut state: MessengerState,
+    chat_id: ChatId,
+    change: impl FnOnce(&mut ChatSettings),
+) {
+    let Some(previous) = state
+        .store
+        .peek(|store| store.chat(chat_id).map(|c| c.settings.clone()))
+    else {
+        return;
+    };
+    let mut settings = previous.clone();
+    change(&mut settings);
+    state
+        .store
+        .apply(|store| store.apply_chat_settings(chat_id, settings.clone()));
+    spawn(async move {
+        match client.update_chat_settings(chat_id, &settings).await {
+            Ok(settings) => {
+                state
+                    .store
+                    .apply(|store| store.apply_chat_settings(chat_id, settings));
+            }
+            Err(e) => {
+                state
+                    .store
+                    .apply(|store| store.apply_chat_settings(chat_id, previous));
+                state.error.set(Some(e.to_string()));
+            }
+        }
+    });
+}
+
+/// Saves the folder, the list shows it once the server has it.
+fn save_folder(client: SharedApiClient, mut state: MessengerState, folder: ChatFolder) {
+    spawn(async move {
+        match client.save_folder(&folder).await {
+            Ok(folder) => {
+                state.store.apply(|store| store.apply_folder(folder));
+            }
+            Err(e) => state.error.set(Some(e.to_string())),
+        }
+    });
+}
+
+/// All chats, the user's folders and the archive, with a form for new folders.
+#[component]
+pub fn ChatTabs(filter: Signal<ChatFilter>) -> Element {
+    let client = use_context::<SharedApiClient>();
+    let mut state = use_context::<MessengerState>();
+    let mut creating = use_signal(|| false);
+    let mut name = use_signal(String::new);
+    let mut include_unread = use_signal(|| false);
+
+    let folders = state.store.folders().read().clone();
+    let tab = move |tab: ChatFilter| {
+        let class = if filter() == tab {
+            "chat-tab selected"
+        } else {
+            "chat-tab"
+        };
+        (class, move |_| filter.set(tab.clone()))
+    };
+    let (all_class, show_all) = tab(ChatFilter::All);
+    let (archived_class, show_archived) = tab(ChatFilter::Archived);
+
+    let delete_client = client.clone();
+    let mut create = move || {
+        let folder_name = name.read().trim().to_string();
+        if folder_name.is_empty() {
+            return;
+        }
+        let folder = ChatFolder {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: folder_name,
+            chat_ids: vec![],
+            include_unread: include_unread(),
+        };
+        creating.set(false);
+        name.set(String::new());
+        include_unread.set(false);
+        save_folder(client.clone(), state, folder);
+    };
+
+    let mut create_on_enter = create.clone();
+
+    rsx! {
+        div { class: "chat-tabs",
+            button { class: all_class, onclick: show_all, "All" }
+            for folder in folders {
+                {
+                    let (class, show) = tab(ChatFilter::Folder(folder.id.clone()));
+                    let is_selected = filter() == ChatFilter::Folder(folder.id.clone());
+                    let client = delete_client.clone();
+                    rsx! {
+                        span { key: "{folder.id}", class: "chat-tab-container",
+                            button { class, onclick: show, "{folder.name}" }
+                            if is_selected {
+                                button {
+                                    class: "chat-tab-delete",
+                                    title: "Delete folder",
+                                    onclick: move |_| {
+                                        let (client, folder_id) = (client.clone(), folder.id.clone());
+                                        spawn(async move {
+                                            match client.delete_folder(&folder_id).await {
+                                                Ok(()) => {
+                                                    filter.set(ChatFilter::All);
+                                                    state.store.apply(|store| store.remove_folder(&folder_id));
+                                                }
+                                                Err(e) => state.error.set(Some(e.to_string())),
+                                            }
+                                        });
+                                    },
+                                    "✕"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            button { class: archived_class, onclick: show_archived, "Archived" }
+            button {
+                class: "chat-tab",
+                title: "New folder",
+                onclick: move |_| creating.toggle(),
+                "+"
+            }
+        }
+        if creating() {
+            div { class: "new-folder",
+                input {
+                    "type": "text",
+                    placeholder: "Folder name, e.g. Work",
+                    value: "{name}",
+                    oninput: move |evt| name.set(evt.value()),
+                    onkeydown: move |evt| match evt.key() {
+                        Key::Enter => create_on_enter(),
+                        Key::Escape => creating.set(false),
+                        _ => {}
+                    },
+                }
+                label {
+                    input {
+                        "type": "checkbox",
+                        checked: include_unread(),
+                        onchange: move |evt| include_unread.set(evt.checked()),
+                    }
+                    "Include all unread chats"
+                }
+                button {
+                    disabled: name.read().trim().is_empty(),
+                    onclick: move |_| create(),
+                    "Create folder"
+                }
+            }
+        }
+    }
+}
+
+/// Pin, archive, mute and folder options of a chat in the list.
+#[component]
+pub fn ChatMenu(chat_id: ChatId, on_close: EventHandler) -> Element {
+    let client = use_context::<SharedApiClient>();
+    let state = use_context::<MessengerState>();
+    let Some(chat) = state.store.chat(chat_id).read().clone() else {
+        return rsx! {};
+    };
+    let folders = state.store.folders().read().clone();
+    let settings = chat.settings.clone();
+    let is_muted = settings.is_muted(now_timestamp());
+
+    let update = move |change: Box<dyn FnOnce(&mut ChatSettings)>| {
+        on_close.call(());
+        update_chat_settings(client.clone(), state, chat_id, change);
+    };
+    let folder_client = use_context::<SharedApiClient>();
+    let pin = update.clone();
+    let archive = update.clone();
+    let unmute = update.clone();
+
+    rsx! {
+        div {
+            class: "chat-menu",
+            onclick: move |evt| evt.stop_propagation(),
+            button {
+                onclick: move |_| pin(Box::new(|s| s.pinned = !s.pinned)),
+                if settings.pinned { "Unpin" } else { "Pin to top" }
+            }
+            button {
+                onclick: move |_| archive(Box::new(|s| s.archived = !s.archived)),
+                if settings.archived { "Unarchive" } else { "Archive" }
+            }
+            if is_muted {
+                button {
+                    onclick: move |_| {
+                        unmute(
+                            Box::new(|s| {
+                                s.muted = false;
+                                s.muted_until = None;
+                            }),
+                        )
+                    },
+                    "Unmute"
+                }
+            } else {
+                div { class: "chat-menu-title", "Mute" }
+                for (label, duration) in MUTE_OPTIONS.iter().copied() {
+                    button {
+                        key: "{label}",
+                        onclick: {
+                            let mute = update.clone();
+                            move |_| {
+                                let until = duration.map(|d| now_timestamp() + d);
+                                mute(
+                                    Box::new(move |s| {
+                                        s.muted = true;
+                                        s.muted_until = until;
+                                    }),
+                                )
+                            }
+                        },
+                        "{label}"
+                    }
+                }
+            }
+            if !folders.is_empty() {
+                div { class: "chat-menu-title", "Folders" }
+            }
+            for folder in folders {
+                button {
+                    key: "{folder.id}",
+                    onclick: {
+                        let client = folder_client.clone();
+                        let mut folder = folder.clone();
+                        move |_| {
+                            on_close.call(());
+                            match folder.chat_ids.iter().position(|id| *id == chat_id) {
+                                Some(i) => {
+                                    folder.chat_ids.remove(i);
+                                }
+                                None => folder.chat_ids.push(chat_id),
+                            }
+                            save_folder(client.clone(), state, folder.clone());
+                        }
+                    },
+                    if folder.chat_ids.contains(&chat_id) { "✓ {folder.name}" } else { "{folder.name}" }
+                }
+            }
+        }
+    }
+}
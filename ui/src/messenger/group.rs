@@ -0,0 +1,258 @@
+use crate::messenger::{
+    InviteLinks, MessengerRoute, MessengerState, NAME, PresenceDot, load_users,
+};
+use dcore::routing::navigate_to_app;
+use dcore::utils::sleep;
+use dioxus::prelude::*;
+use lcore::api::client::{ApiResult, SharedApiClient};
+use lcore::api::schemas::{ChatModel, ChatRole, MessageModel};
+use lcore::helpers::types::ChatId;
+use lcore::messenger::models::ChatAction;
+use std::future::Future;
+use std::time::Duration;
+
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Applies the chat returned by a change and fetches the users it added.
+fn update_chat(
+    client: SharedApiClient,
+    mut state: MessengerState,
+    request: impl Future<Output = ApiResult<ChatModel>> + 'static,
+) {
+    spawn(async move {
+        match request.await {
+            Ok(chat) => {
+                state.store.apply(|store| store.apply_chat(chat));
+                if let Err(e) = load_users(&client, state).await {
+                    state.error.set(Some(e.to_string()));
+                }
+            }
+            Err(e) => state.error.set(Some(e.to_string())),
+        }
+    });
+}
+
+/// Name, members and roles of a group, with the changes the user's role allows.
+#[component]
+pub fn GroupSettings(chat_id: ChatId, on_close: EventHandler) -> Element {
+    let client = use_context::<SharedApiClient>();
+    let mut state = use_context::<MessengerState>();
+    let chat = state.store.chat(chat_id).read().clone();
+    let mut name = use_signal(|| {
+        chat.as_ref()
+            .and_then(|c| c.name.clone())
+            .unwrap_or_default()
+    });
+    let mut leaving = use_signal(|| false);
+    let (Some(chat), Some(me)) = (chat, state.store.me().read().clone()) else {
+        return rsx! {};
+    };
+
+    let mut members = chat.member_ids.clone();
+    // owner first, then admins, then by name
+    members.sort_by_cached_key(|id| {
+        (
+            std::cmp::Reverse(chat.role(id)),
+            state.store.username(id).to_lowercase(),
+        )
+    });
+
+    let rename_client = client.clone();
+    let leave_client = client.clone();
+    let member_client = client.clone();
+    let can_rename = chat.allows(&me, &ChatAction::Rename);
+    let current_name = chat.name.clone().unwrap_or_default();
+
+    rsx! {
+        div { class: "group-settings",
+            div { class: "group-settings-header",
+                "Group info"
+                button {
+                    class: "group-settings-close",
+                    title: "Close",
+                    onclick: move |_| on_close.call(()),
+                    "✕"
+                }
+            }
+            if can_rename {
+                div { class: "group-name",
+                    input {
+                        "type": "text",
+                        placeholder: "Group name",
+                        value: "{name}",
+                        oninput: move |evt| name.set(evt.value()),
+                    }
+                    button {
+                        disabled: name.read().trim() == current_name,
+                        onclick: move |_| {
+                            let name = name.read().trim().to_string();
+                            let name = (!name.is_empty()).then_some(name);
+                            let client = rename_client.clone();
+                            update_chat(client.clone(), state, async move {
+                                client.rename_chat(chat_id, name).await
+                            });
+                        },
+                        "Rename"
+                    }
+                }
+            }
+            div { class: "group-members-title", "{members.len()} members" }
+            for member_id in members {
+                div { key: "{member_id}", class: "group-member",
+                    PresenceDot { user_id: member_id.clone() }
+                    span { class: "group-member-name", "{state.store.username(&member_id)}" }
+                    if let Some(role) = chat.role(&member_id).filter(|r| *r != ChatRole::Member) {
+                        span { class: "group-member-role", "{role.label()}" }
+                    }
+                    if chat.allows(&me, &ChatAction::SetRole(member_id.clone())) {
+                        button {
+                            onclick: {
+                                let (client, member_id) = (member_client.clone(), member_id.clone());
+                                let role = if chat.role(&member_id) == Some(ChatRole::Admin) {
+                                    ChatRole::Member
+                                } else {
+                                    ChatRole::Admin
+                                };
+                                move |_| {
+                                    let (request_client, member_id) = (client.clone(), member_id.clone());
+                                    update_chat(client.clone(), state, async move {
+                                        request_client.set_member_role(chat_id, &member_id, role).await
+                                    });
+                                }
+                            },
+                            if chat.role(&member_id) == Some(ChatRole::Admin) {
+                                "Remove admin"
+                            } else {
+                                "Make admin"
+                            }
+                        }
+                    }
+                    if chat.allows(&me, &ChatAction::RemoveMember(member_id.clone())) {
+                        button {
+                            onclick: {
+                                let (client, member_id) = (member_client.clone(), member_id.clone());
+                                move |_| {
+                                    let (request_client, member_id) = (client.clone(), member_id.clone());
+                                    update_chat(client.clone(), state, async move {
+                                        request_client.remove_member(chat_id, &member_id).await
+                                    });
+                                }
+                            },
+                            "Remove"
+                        }
+                    }
+                }
+            }
+            if chat.allows(&me, &ChatAction::AddMembers) {
+                AddMembers { chat_id, member_ids: chat.member_ids.clone() }
+                InviteLinks { chat_id }
+            }
+            if chat.allows(&me, &ChatAction::Leave) {
+                div { class: "group-leave",
+                    if leaving() {
+                        "Leave the group?"
+                        button {
+                            class: "danger",
+                            onclick: move |_| {
+                                leaving.set(false);
+                                let client = leave_client.clone();
+                                spawn(async move {
+                                    match client.leave_chat(chat_id).await {
+                                        Ok(()) => {
+                                            state.store.apply(|store| store.remove_chat(chat_id));
+                                            navigate_to_app(NAME, &MessengerRoute::Chats.segments());
+                                        }
+                                        Err(e) => state.error.set(Some(e.to_string())),
+                                    }
+                                });
+                            },
+                            "Leave"
+                        }
+                        button { onclick: move |_| leaving.set(false), "Cancel" }
+                    } else {
+                        button {
+                            class: "danger",
+                            onclick: move |_| leaving.set(true),
+                            "Leave group"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Searches users that aren't members yet.
+#[component]
+fn AddMembers(chat_id: ChatId, member_ids: Vec<String>) -> Element {
+    let client = use_context::<SharedApiClient>();
+    let state = use_context::<MessengerState>();
+    let mut query = use_signal(String::new);
+
+    let search_client = client.clone();
+    // a new query restarts the resource, which cancels the pending sleep
+    let results = use_resource(move || {
+        let client = search_client.clone();
+        let query = query.read().trim().to_string();
+        async move {
+            if query.is_empty() {
+                return Ok(vec![]);
+            }
+            sleep(SEARCH_DEBOUNCE).await;
+            client.search_users(query).await.map(|r| r.users)
+        }
+    });
+    let users = match results.read().as_ref() {
+        Some(Ok(users)) => users
+            .iter()
+            .filter(|u| !member_ids.contains(&u.id))
+            .cloned()
+            .collect(),
+        _ => vec![],
+    };
+
+    rsx! {
+        div { class: "group-add-members",
+            input {
+                "type": "text",
+                placeholder: "Add members",
+                value: "{query}",
+                oninput: move |evt| query.set(evt.value()),
+            }
+            for user in users {
+                div { key: "{user.id}", class: "group-member",
+                    span { class: "group-member-name", "{user.username}" }
+                    button {
+                        onclick: {
+                            let client = client.clone();
+                            move |_| {
+                                query.set(String::new());
+                                let (request_client, user_id) = (client.clone(), user.id.clone());
+                                update_chat(client.clone(), state, async move {
+                                    request_client.add_members(chat_id, vec![user_id]).await
+                                });
+                            }
+                        },
+                        "Add"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A change to the chat in the timeline, e.g. "jane added bob".
+#[component]
+pub fn SystemMessage(message: MessageModel) -> Element {
+    let state = use_context::<MessengerState>();
+    let users = state.store.users();
+    let _users = users.read();
+    let text = state
+        .store
+        .peek(|store| store.system_text(&message))
+        .unwrap_or_default();
+
+    rsx! {
+        div { id: "message-{message.id}", class: "system-message", "{text}" }
+    }
+}
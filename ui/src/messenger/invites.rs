@@ -0,0 +1,280 @@
+use crate::messenger::{MessengerRoute, MessengerState, NAME, load_users};
+use dcore::routing::{app_path, navigate_to_app, replace_app_route};
+use dioxus::prelude::*;
+use lcore::api::client::SharedApiClient;
+use lcore::api::schemas::{ChatInvite, NewChatInvite};
+use lcore::helpers::types::ChatId;
+use lcore::messenger::invites::parse_invite_code;
+use lcore::utils::{format_timestamp, now_timestamp};
+
+const EXPIRY_OPTIONS: &[(&str, Option<u64>)] = &[
+    ("1 hour", Some(3600)),
+    ("1 day", Some(86400)),
+    ("7 days", Some(7 * 86400)),
+    ("Never", None),
+];
+const MAX_USES_OPTIONS: &[(&str, Option<u32>)] = &[
+    ("1 use", Some(1)),
+    ("5 uses", Some(5)),
+    ("25 uses", Some(25)),
+    ("No limit", None),
+];
+
+const COPY_JS: &str = r#"
+    const text = await dioxus.recv();
+    await navigator.clipboard.writeText(text);
+"#;
+
+fn invite_path(code: &str) -> String {
+    let route = MessengerRoute::Join {
+        code: code.to_string(),
+    };
+    app_path(NAME, &route.segments())
+}
+
+/// Invite links of a group, for admins.
+#[component]
+pub fn InviteLinks(chat_id: ChatId) -> Element {
+    let client = use_context::<SharedApiClient>();
+    let mut state = use_context::<MessengerState>();
+    let mut invites = use_signal(Vec::<ChatInvite>::new);
+    let mut expiry = use_signal(|| 1);
+    let mut max_uses = use_signal(|| MAX_USES_OPTIONS.len() - 1);
+
+    let load_client = client.clone();
+    use_effect(use_reactive!(|chat_id| {
+        let client = load_client.clone();
+        spawn(async move {
+            match client.get_invites(chat_id).await {
+                Ok(result) => invites.set(result.invites),
+                Err(e) => state.error.set(Some(e.to_string())),
+            }
+        });
+    }));
+    // the links are shared outside the app, so they need the host
+    let origin = use_resource(|| async {
+        document::eval("return window.location.origin")
+            .join::<String>()
+            .await
+            .unwrap_or_default()
+    });
+    let origin = origin.read().clone().unwrap_or_default();
+
+    let create_client = client.clone();
+    let now = now_timestamp();
+
+    rsx! {
+        div { class: "invite-links",
+            div { class: "group-members-title", "Invite links" }
+            for invite in invites.read().iter().filter(|i| i.is_active(now)).cloned() {
+                div { key: "{invite.code}", class: "invite-link",
+                    span { class: "invite-link-url", "{origin}{invite_path(&invite.code)}" }
+                    span { class: "invite-link-info",
+                        match invite.max_uses {
+                            Some(max_uses) => format!("{}/{} used", invite.uses, max_uses),
+                            None => format!("{} used", invite.uses),
+                        }
+                        ", "
+                        match invite.expires_at {
+                            Some(expires_at) => format!("expires {}", format_timestamp(expires_at)),
+                            None => "doesn't expire".to_string(),
+                        }
+                    }
+                    button {
+                        onclick: {
+                            let link = format!("{}{}", origin, invite_path(&invite.code));
+                            move |_| {
+                                let _ = document::eval(COPY_JS).send(link.clone());
+                            }
+                        },
+                        "Copy"
+                    }
+                    button {
+                        class: "danger",
+                        onclick: {
+                            let client = client.clone();
+                            move |_| {
+                                let (client, code) = (client.clone(), invite.code.clone());
+                                spawn(async move {
+                                    match client.revoke_invite(chat_id, &code).await {
+                                        Ok(()) => invites.write().retain(|i| i.code != code),
+                                        Err(e) => state.error.set(Some(e.to_string())),
+                                    }
+                                });
+                            }
+                        },
+                        "Revoke"
+                    }
+                }
+            }
+            div { class: "invite-new",
+                select {
+                    onchange: move |evt| expiry.set(evt.value().parse().unwrap_or(0)),
+                    for (i, (label, _)) in EXPIRY_OPTIONS.iter().enumerate() {
+                        option { value: "{i}", selected: expiry() == i, "{label}" }
+                    }
+                }
+                select {
+                    onchange: move |evt| max_uses.set(evt.value().parse().unwrap_or(0)),
+                    for (i, (label, _)) in MAX_USES_OPTIONS.iter().enumerate() {
+                        option { value: "{i}", selected: max_uses() == i, "{label}" }
+                    }
+                }
+                button {
+                    onclick: move |_| {
+                        let client = create_client.clone();
+                        let invite = NewChatInvite {
+                            expires_in: EXPIRY_OPTIONS[expiry()].1,
+                            max_uses: MAX_USES_OPTIONS[max_uses()].1,
+                        };
+                        spawn(async move {
+                            match client.create_invite(chat_id, invite).await {
+                                Ok(invite) => invites.push(invite),
+                                Err(e) => state.error.set(Some(e.to_string())),
+                            }
+                        });
+                    },
+                    "Create link"
+                }
+            }
+        }
+    }
+}
+
+/// Where invite links lead, the chat behind the code with a button to join it.
+///
+/// Members are sent on to the chat right away.
+#[component]
+pub fn JoinChat(code: String) -> Element {
+    let client = use_context::<SharedApiClient>();
+    let mut state = use_context::<MessengerState>();
+    let mut joining = use_signal(|| false);
+
+    let preview_client = client.clone();
+    let preview = use_resource(use_reactive!(|code| {
+        let client = preview_client.clone();
+        async move { client.get_invite(&code).await }
+    }));
+    let chat_id = preview
+        .read()
+        .as_ref()
+        .and_then(|p| p.as_ref().ok())
+        .map(|p| p.chat_id);
+    let is_member = chat_id.is_some_and(|id| state.store.chat(id).read().is_some());
+    use_effect(use_reactive!(|chat_id, is_member| {
+        if let Some(chat_id) = chat_id.filter(|_| is_member) {
+            let route = MessengerRoute::Chat {
+                chat_id,
+                message_id: None,
+            };
+            replace_app_route(NAME, &route.segments());
+        }
+    }));
+
+    let join = move |_| {
+        let (client, code) = (client.clone(), code.clone());
+        joining.set(true);
+        spawn(async move {
+            match client.join_chat(&code).await {
+                Ok(chat) => {
+                    let route = MessengerRoute::Chat {
+                        chat_id: chat.id,
+                        message_id: None,
+                    };
+                    state.store.apply(|store| store.apply_chat(chat));
+                    replace_app_route(NAME, &route.segments());
+                    if let Err(e) = load_users(&client, state).await {
+                        state.error.set(Some(e.to_string()));
+                    }
+                }
+                Err(e) => state.error.set(Some(e.to_string())),
+            }
+            joining.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "conversation-area",
+            div { class: "join-chat",
+                match preview.read().as_ref() {
+                    None => rsx! { "Loading invite..." },
+                    Some(Err(e)) => rsx! {
+                        div { class: "join-chat-title", "This invite can't be used" }
+                        div { class: "messenger-error", "{e}" }
+                    },
+                    Some(Ok(preview)) => rsx! {
+                        div { class: "join-chat-title",
+                            {preview.name.clone().unwrap_or("Unnamed group".to_string())}
+                        }
+                        div { class: "join-chat-info", "{preview.member_count} members" }
+                        if let Some(expires_at) = preview.expires_at {
+                            div { class: "join-chat-info",
+                                "Invite expires {format_timestamp(expires_at)}"
+                            }
+                        }
+                        button {
+                            class: "message-send-button",
+                            disabled: joining(),
+                            onclick: join.clone(),
+                            "Join chat"
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Opens the preview of an invite from its code or link.
+#[component]
+pub fn JoinWithCode() -> Element {
+    let mut open = use_signal(|| false);
+    let mut input = use_signal(String::new);
+    let mut invalid = use_signal(|| false);
+
+    let mut submit = move || {
+        let Some(code) = parse_invite_code(&input.read()) else {
+            invalid.set(true);
+            return;
+        };
+        open.set(false);
+        input.set(String::new());
+        navigate_to_app(NAME, &MessengerRoute::Join { code }.segments());
+    };
+
+    if !open() {
+        return rsx! {
+            button {
+                class: "join-with-code-button",
+                onclick: move |_| open.set(true),
+                "Join with code"
+            }
+        };
+    }
+
+    rsx! {
+        div { class: "join-with-code",
+            input {
+                "type": "text",
+                placeholder: "Invite code or link",
+                value: "{input}",
+                onmounted: move |evt| async move {
+                    let _ = evt.set_focus(true).await;
+                },
+                oninput: move |evt| {
+                    invalid.set(false);
+                    input.set(evt.value());
+                },
+                onkeydown: move |evt| match evt.key() {
+                    Key::Enter => submit(),
+                    Key::Escape => open.set(false),
+                    _ => {}
+                },
+            }
+            button { onclick: move |_| submit(), "Join" }
+            if invalid() {
+                div { class: "messenger-error", "Not an invite code or link" }
+            }
+        }
+    }
+}
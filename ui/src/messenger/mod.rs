@@ -1,3 +1,27 @@
 mod app;
+mod attachments;
+mod chat;
+mod chat_list;
+mod folders;
+mod group;
+mod invites;
+mod presence;
+mod receipts;
+mod routes;
+mod search;
+mod switcher;
 
 pub use app::*;
+pub use attachments::*;
+pub use chat::*;
+pub use chat_list::*;
+pub use folders::*;
+pub use group::*;
+pub use invites::*;
+pub use presence::*;
+pub use receipts::*;
+pub use routes::MessengerRoute;
+pub use search::*;
+pub use switcher::*;
+
+dcore::export_app!(NAME, MessengerApp);
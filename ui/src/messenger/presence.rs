@@ -0,0 +1,78 @@
+use crate::messenger::MessengerState;
+use dcore::events::publish;
+use dioxus::prelude::*;
+use lcore::api::schemas::{PresenceStatus, WsCommand};
+use lcore::helpers::types::{ChatId, UserId};
+use lcore::utils::format_timestamp;
+
+// reports whether the page is visible, now and on every change
+const VISIBILITY_JS: &str = r#"
+    dioxus.send(document.visibilityState);
+    document.addEventListener('visibilitychange', () => dioxus.send(document.visibilityState));
+    await new Promise(() => {});
+"#;
+
+/// Tells the others that the user is online while the page is visible, and away otherwise.
+pub fn use_own_presence() {
+    use_hook(|| {
+        spawn(async move {
+            let mut eval = document::eval(VISIBILITY_JS);
+            while let Ok(visibility) = eval.recv::<String>().await {
+                let status = if visibility == "visible" {
+                    PresenceStatus::Online
+                } else {
+                    PresenceStatus::Away
+                };
+                publish(WsCommand::Presence { status });
+            }
+        });
+    });
+}
+
+#[component]
+pub fn PresenceDot(user_id: UserId) -> Element {
+    let state = use_context::<MessengerState>();
+    let presence = state.store.presence().read().get(&user_id).cloned();
+    let Some(presence) = presence else {
+        return rsx! {};
+    };
+
+    let (class, title) = match presence.status {
+        PresenceStatus::Online => ("presence-dot online", "Online".to_string()),
+        PresenceStatus::Away => ("presence-dot away", "Away".to_string()),
+        PresenceStatus::Offline => (
+            "presence-dot offline",
+            presence.last_seen.map_or("Offline".to_string(), |t| {
+                format!("Last seen {}", format_timestamp(t))
+            }),
+        ),
+    };
+
+    rsx! {
+        span { class, title }
+    }
+}
+
+/// "Alice is typing…" under the chat title, empty while nobody is.
+#[component]
+pub fn TypingIndicator(chat_id: ChatId) -> Element {
+    let state = use_context::<MessengerState>();
+    let names: Vec<String> = state
+        .store
+        .typing(chat_id)
+        .read()
+        .iter()
+        .map(|id| state.store.username(id))
+        .collect();
+
+    let text = match names.as_slice() {
+        [] => return rsx! {},
+        [name] => format!("{} is typing…", name),
+        [first, second] => format!("{} and {} are typing…", first, second),
+        _ => "Several people are typing…".to_string(),
+    };
+
+    rsx! {
+        div { class: "typing-indicator", "{text}" }
+    }
+}
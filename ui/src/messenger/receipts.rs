@@ -0,0 +1,83 @@
+use crate::messenger::MessengerState;
+use dioxus::prelude::*;
+use lcore::api::schemas::MessageModel;
+use lcore::helpers::types::{MessageId, UserId};
+use std::collections::HashMap;
+
+const MAX_SEEN_AVATARS: usize = 5;
+
+/// For each loaded message, the other members whose last read message it is.
+///
+/// Every reader shows up once, under the furthest message they have read, like in most
+/// messengers. Watermarks past the loaded messages land on the last one.
+pub fn seen_positions(
+    messages: &[MessageModel],
+    watermarks: &HashMap<UserId, u64>,
+    me: Option<&UserId>,
+) -> HashMap<MessageId, Vec<UserId>> {
+    let mut positions: HashMap<MessageId, Vec<UserId>> = HashMap::new();
+    for (user_id, seq) in watermarks.iter().filter(|(id, _)| Some(*id) != me) {
+        let read = messages.partition_point(|m| m.seq <= *seq);
+        // pending messages sort after everything, deleted ones don't show the avatars
+        let Some(message) = messages[..read].iter().rev().find(|m| !m.deleted) else {
+            continue;
+        };
+        positions
+            .entry(message.id.clone())
+            .or_default()
+            .push(user_id.clone());
+    }
+    positions.values_mut().for_each(|ids| ids.sort());
+    positions
+}
+
+/// The initials of a user in a circle.
+#[component]
+pub fn UserAvatar(user_id: UserId) -> Element {
+    let state = use_context::<MessengerState>();
+    let name = state.store.username(&user_id);
+    let initials: String = name.chars().take(2).collect::<String>().to_uppercase();
+
+    rsx! {
+        span { class: "user-avatar", title: "{name}", "{initials}" }
+    }
+}
+
+/// "Seen by" avatars under a message, clicking them lists everyone who has read it.
+#[component]
+pub fn SeenBy(message: MessageModel, user_ids: Vec<UserId>) -> Element {
+    let state = use_context::<MessengerState>();
+    let mut open = use_signal(|| false);
+    // subscribes to the watermarks, the list changes when someone catches up
+    let watermarks = state.store.read_watermarks(message.chat_id);
+    let _watermarks = watermarks.read();
+    let readers = state.store.peek(|store| store.readers(&message));
+
+    let hidden = user_ids.len().saturating_sub(MAX_SEEN_AVATARS);
+    rsx! {
+        div { class: "seen-by-container",
+            button {
+                class: "seen-by",
+                title: "Seen by",
+                onclick: move |_| open.toggle(),
+                for user_id in user_ids.iter().take(MAX_SEEN_AVATARS).cloned() {
+                    UserAvatar { key: "{user_id}", user_id }
+                }
+                if hidden > 0 {
+                    span { class: "seen-by-more", "+{hidden}" }
+                }
+            }
+            if open() {
+                div { class: "seen-by-popover", onclick: move |_| open.set(false),
+                    div { class: "seen-by-title", "Seen by" }
+                    for user_id in readers {
+                        div { key: "{user_id}", class: "seen-by-reader",
+                            UserAvatar { user_id: user_id.clone() }
+                            "{state.store.username(&user_id)}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
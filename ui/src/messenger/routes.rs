@@ -0,0 +1,139 @@
+use lcore::helpers::types::{ChatId, MessageId};
+use lcore::messenger::invites::{JOIN_SEGMENT, parse_invite_code};
+
+/// Messenger part of the url, everything after `/apps/messenger`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MessengerRoute {
+    Chats,
+    Chat {
+        chat_id: ChatId,
+        message_id: Option<MessageId>,
+    },
+    /// Compose view of a chat that doesn't exist yet, created with its first message.
+    NewChat,
+    /// Preview of the chat behind an invite code, where invite links lead.
+    Join {
+        code: String,
+    },
+}
+
+impl MessengerRoute {
+    pub fn parse(segments: &[String]) -> Option<Self> {
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        match segments.as_slice() {
+            [] | ["chats"] => Some(Self::Chats),
+            ["new"] => Some(Self::NewChat),
+            // the code is sent to the server, so only well-formed ones make it a route
+            [JOIN_SEGMENT, code] => Some(Self::Join {
+                code: parse_invite_code(code)?,
+            }),
+            ["chats", chat_id] => Some(Self::Chat {
+                chat_id: chat_id.parse().ok()?,
+                message_id: None,
+            }),
+            ["chats", chat_id, "messages", message_id] => Some(Self::Chat {
+                chat_id: chat_id.parse().ok()?,
+                message_id: Some(message_id.to_string()),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn segments(&self) -> Vec<String> {
+        match self {
+            Self::Chats => vec![],
+            Self::NewChat => vec!["new".to_string()],
+            Self::Join { code } => vec![JOIN_SEGMENT.to_string(), code.clone()],
+            Self::Chat {
+                chat_id,
+                message_id: None,
+            } => vec!["chats".to_string(), chat_id.to_string()],
+            Self::Chat {
+                chat_id,
+                message_id: Some(message_id),
+            } => vec![
+                "chats".to_string(),
+                chat_id.to_string(),
+                "messages".to_string(),
+                message_id.clone(),
+            ],
+        }
+    }
+
+    pub fn chat_id(&self) -> Option<ChatId> {
+        match self {
+            Self::Chat { chat_id, .. } => Some(*chat_id),
+            Self::Chats | Self::NewChat | Self::Join { .. } => None,
+        }
+    }
+
+    pub fn message_id(&self) -> Option<&str> {
+        match self {
+            Self::Chat { message_id, .. } => message_id.as_deref(),
+            Self::Chats | Self::NewChat | Self::Join { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(path: &[&str]) -> Vec<String> {
+        path.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_and_segments_round_trip() {
+        let routes = [
+            MessengerRoute::Chats,
+            MessengerRoute::NewChat,
+            MessengerRoute::Join {
+                code: "Ab-3_x".to_string(),
+            },
+            MessengerRoute::Chat {
+                chat_id: 7,
+                message_id: None,
+            },
+            MessengerRoute::Chat {
+                chat_id: 7,
+                message_id: Some("m/1?".to_string()),
+            },
+        ];
+        for route in routes {
+            assert_eq!(MessengerRoute::parse(&route.segments()), Some(route));
+        }
+        assert_eq!(
+            MessengerRoute::parse(&segments(&["chats"])),
+            Some(MessengerRoute::Chats)
+        );
+    }
+
+    #[test]
+    fn test_invalid_chat_id() {
+        assert_eq!(MessengerRoute::parse(&segments(&["chats", "abc"])), None);
+        assert_eq!(MessengerRoute::parse(&segments(&["chats", "-1"])), None);
+        assert_eq!(
+            MessengerRoute::parse(&segments(&["chats", "x", "messages", "m1"])),
+            None
+        );
+        assert_eq!(
+            MessengerRoute::parse(&segments(&["chats", "1", "other"])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_join_with_invalid_code() {
+        assert_eq!(
+            MessengerRoute::parse(&segments(&[JOIN_SEGMENT, "a b"])),
+            None
+        );
+        assert_eq!(
+            MessengerRoute::parse(&segments(&[JOIN_SEGMENT, "../x"])),
+            None
+        );
+        assert_eq!(MessengerRoute::parse(&segments(&[JOIN_SEGMENT, ""])), None);
+        assert_eq!(MessengerRoute::parse(&segments(&[JOIN_SEGMENT])), None);
+    }
+}
@@ -0,0 +1,384 @@
+use crate::generic::ShortBorder;
+use crate::messenger::{
+    CSS, MessageInput, MessengerRoute, MessengerState, NAME, PresenceDot, load_users, show_message,
+};
+use dcore::routing::navigate_to_app;
+use dcore::utils::sleep;
+use dioxus::prelude::*;
+use lcore::api::client::SharedApiClient;
+use lcore::api::schemas::{MessageModel, NewChatModel, User};
+use lcore::messenger::search::{group_by_chat, snippet};
+use lcore::utils::format_timestamp;
+use std::time::Duration;
+
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(300);
+const MESSAGE_SEARCH_LIMIT: usize = 50;
+const SNIPPET_LENGTH: usize = 80;
+
+/// Members and name of a chat that is created with its first message.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChatDraft {
+    pub name: String,
+    pub members: Vec<User>,
+}
+
+#[component]
+pub fn SearchBar(query: Signal<String>) -> Element {
+    rsx! {
+        div { class: "search-bar",
+            input {
+                "type": "text",
+                placeholder: "Search",
+                value: "{query}",
+                oninput: move |evt| query.set(evt.value()),
+            }
+        }
+    }
+}
+
+/// Users matching the query, searched once the user stops typing.
+#[component]
+pub fn UserSearchResults(query: Signal<String>, selected: Signal<Vec<User>>) -> Element {
+    let client = use_context::<SharedApiClient>();
+    let mut state = use_context::<MessengerState>();
+
+    // a new query restarts the resource, which cancels the pending sleep
+    let results = use_resource(move || {
+        let client = client.clone();
+        let query = query.read().trim().to_string();
+        async move {
+            sleep(SEARCH_DEBOUNCE).await;
+            client.search_users(query).await.map(|r| r.users)
+        }
+    });
+    use_effect(move || {
+        if let Some(Ok(users)) = results.read().as_ref() {
+            let users = users.clone();
+            state.store.apply(|store| store.apply_users(users));
+        }
+    });
+
+    let me = state.store.me().read().clone();
+    let open = move |user: User| {
+        query.set(String::new());
+        selected.set(vec![]);
+        let route = match state.store.peek(|store| store.direct_chat_with(&user.id)) {
+            Some(chat_id) => MessengerRoute::Chat {
+                chat_id,
+                message_id: None,
+            },
+            None => {
+                state.draft.set(Some(ChatDraft {
+                    name: String::new(),
+                    members: vec![user],
+                }));
+                MessengerRoute::NewChat
+            }
+        };
+        navigate_to_app(NAME, &route.segments());
+    };
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: CSS }
+        div { class: "chat-list",
+            match results.read().as_ref() {
+                None => rsx! {
+                    div { class: "no-chats", "Searching..." }
+                },
+                Some(Err(e)) => rsx! {
+                    div { class: "messenger-error", "{e}" }
+                },
+                Some(Ok(users)) if users.is_empty() => rsx! {
+                    div { class: "no-chats", "No users found" }
+                },
+                Some(Ok(users)) => rsx! {
+                    for user in users.iter().filter(|u| Some(&u.id) != me.as_ref()).cloned() {
+                        UserItem {
+                            key: "{user.id}",
+                            is_selected: selected.read().iter().any(|u| u.id == user.id),
+                            user: user.clone(),
+                            on_open: open,
+                            on_toggle: move |user: User| {
+                                let mut selected = selected.write();
+                                match selected.iter().position(|u| u.id == user.id) {
+                                    Some(i) => {
+                                        selected.remove(i);
+                                    }
+                                    None => selected.push(user),
+                                }
+                            },
+                        }
+                        ShortBorder {}
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Messages matching the query, grouped by chat.
+///
+/// The loaded messages are searched right away, the server is only asked once the user stops
+/// typing and there aren't enough of them. Servers without message search just leave the
+/// local results.
+#[component]
+pub fn MessageSearchResults(query: Signal<String>) -> Element {
+    let client = use_context::<SharedApiClient>();
+    let state = use_context::<MessengerState>();
+    let query_text = query.read().trim().to_string();
+    // new messages reorder the chats
+    let chat_ids = state.store.chat_ids();
+    let _chat_ids = chat_ids.read();
+    let local: Vec<MessageModel> = state.store.peek(|store| {
+        store
+            .search_messages(&query_text, MESSAGE_SEARCH_LIMIT)
+            .into_iter()
+            .cloned()
+            .collect()
+    });
+
+    let search_client = client.clone();
+    let remote = use_resource(move || {
+        let client = search_client.clone();
+        let query = query.read().trim().to_string();
+        let found = state
+            .store
+            .peek(|store| store.search_messages(&query, MESSAGE_SEARCH_LIMIT).len());
+        async move {
+            if query.is_empty() || found >= MESSAGE_SEARCH_LIMIT {
+                return vec![];
+            }
+            sleep(SEARCH_DEBOUNCE).await;
+            client
+                .search_messages(query, MESSAGE_SEARCH_LIMIT as u32)
+                .await
+                .map_or(vec![], |r| r.messages)
+        }
+    });
+
+    let mut messages = local;
+    if let Some(remote) = remote.read().as_ref() {
+        for message in remote {
+            if !message.deleted && !messages.iter().any(|m| m.id == message.id) {
+                messages.push(message.clone());
+            }
+        }
+    }
+    messages.sort_by(|a, b| b.created_at.total_cmp(&a.created_at));
+    messages.truncate(MESSAGE_SEARCH_LIMIT);
+    let groups: Vec<(String, Vec<MessageModel>)> = group_by_chat(messages)
+        .into_iter()
+        .filter_map(|(chat_id, messages)| {
+            let chat = state.store.peek(|store| store.chat(chat_id).cloned())?;
+            Some((state.store.chat_title(&chat), messages))
+        })
+        .collect();
+
+    let open = move |message: MessageModel| {
+        let mut query = query;
+        query.set(String::new());
+        let route = MessengerRoute::Chat {
+            chat_id: message.chat_id,
+            message_id: Some(message.id.clone()),
+        };
+        navigate_to_app(NAME, &route.segments());
+        spawn(show_message(
+            client.clone(),
+            state,
+            message.chat_id,
+            message.id,
+            message.seq,
+        ));
+    };
+
+    rsx! {
+        div { class: "chat-list message-results",
+            div { class: "search-section-title", "Messages" }
+            if groups.is_empty() {
+                div { class: "no-chats",
+                    if remote.read().is_none() { "Searching..." } else { "No messages found" }
+                }
+            }
+            for (title, messages) in groups {
+                div { key: "{messages[0].chat_id}", class: "message-result-chat", "{title}" }
+                for message in messages {
+                    div {
+                        key: "{message.id}",
+                        class: "chat-item message-result",
+                        onclick: {
+                            let (open, message) = (open.clone(), message.clone());
+                            move |_| open(message.clone())
+                        },
+                        div { class: "chat-info",
+                            div { class: "message-result-author",
+                                "{state.store.username(&message.sender_id)}"
+                            }
+                            div { class: "chat-preview",
+                                for part in snippet(&message.text, &query_text, SNIPPET_LENGTH) {
+                                    if part.highlighted {
+                                        mark { "{part.text}" }
+                                    } else {
+                                        "{part.text}"
+                                    }
+                                }
+                            }
+                        }
+                        div { class: "chat-time", "{format_timestamp(message.created_at)}" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn UserItem(
+    user: User,
+    is_selected: bool,
+    on_open: EventHandler<User>,
+    on_toggle: EventHandler<User>,
+) -> Element {
+    let toggled = user.clone();
+
+    rsx! {
+        div {
+            class: if is_selected { "chat-item selected" } else { "chat-item" },
+            onclick: move |_| on_open.call(user.clone()),
+            div { class: "chat-info",
+                div { class: "chat-title",
+                    PresenceDot { user_id: user.id.clone() }
+                    "{user.username}"
+                }
+            }
+            button {
+                class: "user-select-button",
+                title: if is_selected { "Remove from group" } else { "Add to group" },
+                onclick: move |evt| {
+                    evt.stop_propagation();
+                    on_toggle.call(toggled.clone());
+                },
+                if is_selected { "✓" } else { "+" }
+            }
+        }
+    }
+}
+
+/// Shown while users are selected for a group chat.
+#[component]
+pub fn NewGroupBar(query: Signal<String>, selected: Signal<Vec<User>>) -> Element {
+    let mut state = use_context::<MessengerState>();
+    let mut name = use_signal(String::new);
+
+    let members = selected
+        .read()
+        .iter()
+        .map(|u| u.username.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    rsx! {
+        div { class: "new-group",
+            div { class: "new-group-members", "{members}" }
+            input {
+                class: "new-group-name",
+                "type": "text",
+                placeholder: "Group name",
+                value: "{name}",
+                oninput: move |evt| name.set(evt.value()),
+            }
+            button {
+                class: "message-send-button",
+                disabled: name.read().trim().is_empty(),
+                onclick: move |_| {
+                    state.draft.set(Some(ChatDraft {
+                        name: name.read().trim().to_string(),
+                        members: selected.read().clone(),
+                    }));
+                    name.set(String::new());
+                    query.set(String::new());
+                    selected.set(vec![]);
+                    navigate_to_app(NAME, &MessengerRoute::NewChat.segments());
+                },
+                "Create group"
+            }
+        }
+    }
+}
+
+/// Creates the drafted chat when its first message is sent.
+#[component]
+pub fn ComposeChat() -> Element {
+    let client = use_context::<SharedApiClient>();
+    let mut state = use_context::<MessengerState>();
+
+    let Some(draft) = state.draft.read().clone() else {
+        return rsx! {
+            div { class: "conversation-area",
+                div {
+                    class: "no-chat-selected",
+                    "Search for users to start a chat!"
+                }
+            }
+        };
+    };
+    let title = if draft.name.is_empty() {
+        draft
+            .members
+            .iter()
+            .map(|u| u.username.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    } else {
+        draft.name.clone()
+    };
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: CSS }
+
+        div { class: "conversation-area",
+            div { class: "chat visible",
+                div { class: "chat-header",
+                    "{title}"
+                }
+                div { class: "chat-messages",
+                    div { class: "no-chats", "Send a message to start the chat" }
+                }
+                MessageInput {
+                    on_send: move |first_message: String| {
+                        let client = client.clone();
+                        let draft = draft.clone();
+                        spawn(async move {
+                            let mut member_ids: Vec<String> =
+                                draft.members.iter().map(|u| u.id.clone()).collect();
+                            if let Some(me) = client.user_id().await
+                                && !member_ids.contains(&me)
+                            {
+                                member_ids.push(me);
+                            }
+                            let chat = NewChatModel {
+                                name: (!draft.name.is_empty()).then_some(draft.name),
+                                member_ids,
+                                first_message,
+                            };
+                            let chat = match client.create_chat(chat).await {
+                                Ok(chat) => chat,
+                                Err(e) => {
+                                    state.error.set(Some(e.to_string()));
+                                    return;
+                                }
+                            };
+                            let chat_id = chat.id;
+                            state.store.apply(|store| store.apply_chat(chat));
+                            state.draft.set(None);
+                            let route = MessengerRoute::Chat { chat_id, message_id: None };
+                            navigate_to_app(NAME, &route.segments());
+                            if let Err(e) = load_users(&client, state).await {
+                                state.error.set(Some(e.to_string()));
+                            }
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::messenger::{CSS, MessengerRoute, MessengerState, NAME};
+use dcore::routing::navigate_to_app;
+use dioxus::prelude::*;
+use lcore::helpers::types::ChatId;
+
+/// Ctrl+K style chat switcher: type to filter the chats, arrows to pick one, Enter to open it.
+#[component]
+pub fn QuickSwitcher(open: Signal<bool>) -> Element {
+    let state = use_context::<MessengerState>();
+    let mut query = use_signal(String::new);
+    let mut selected = use_signal(|| 0usize);
+
+    let needle = query.read().trim().to_lowercase();
+    let matches: Vec<(ChatId, String)> = state
+        .store
+        .chat_ids()
+        .read()
+        .iter()
+        .filter_map(|id| {
+            let chat = state.store.chat(*id).read().clone()?;
+            Some((*id, state.store.chat_title(&chat)))
+        })
+        .filter(|(_, title)| title.to_lowercase().contains(&needle))
+        .collect();
+    let current = (*selected.read()).min(matches.len().saturating_sub(1));
+
+    let chat_ids: Vec<ChatId> = matches.iter().map(|(id, _)| *id).collect();
+    let mut close = move || {
+        query.set(String::new());
+        selected.set(0);
+        open.set(false);
+    };
+    let mut pick = move |chat_id: ChatId| {
+        close();
+        let route = MessengerRoute::Chat {
+            chat_id,
+            message_id: None,
+        };
+        navigate_to_app(NAME, &route.segments());
+    };
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: CSS }
+
+        div { class: "quick-switcher-overlay", onclick: move |_| close(),
+            div {
+                class: "quick-switcher",
+                onclick: move |evt| evt.stop_propagation(),
+                input {
+                    class: "quick-switcher-input",
+                    "type": "text",
+                    placeholder: "Go to chat",
+                    value: "{query}",
+                    onmounted: move |evt| async move {
+                        let _ = evt.set_focus(true).await;
+                    },
+                    oninput: move |evt| {
+                        query.set(evt.value());
+                        selected.set(0);
+                    },
+                    onkeydown: move |evt| match evt.key() {
+                        Key::ArrowDown if !chat_ids.is_empty() => {
+                            evt.prevent_default();
+                            selected.set((current + 1) % chat_ids.len());
+                        }
+                        Key::ArrowUp if !chat_ids.is_empty() => {
+                            evt.prevent_default();
+                            selected.set((current + chat_ids.len() - 1) % chat_ids.len());
+                        }
+                        Key::Enter => {
+                            if let Some(chat_id) = chat_ids.get(current) {
+                                pick(*chat_id);
+                            }
+                        }
+                        Key::Escape => close(),
+                        _ => {}
+                    },
+                }
+                div { class: "quick-switcher-results",
+                    for (i, (chat_id, title)) in matches.into_iter().enumerate() {
+                        div {
+                            key: "{chat_id}",
+                            class: if i == current { "chat-item selected" } else { "chat-item" },
+                            onclick: move |_| pick(chat_id),
+                            div { class: "chat-title", "{title}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
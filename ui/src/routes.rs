@@ -0,0 +1,30 @@
+use crate::home::{AppPage, Home, NotFound, Shell};
+use crate::login::LoginPage;
+use dioxus::prelude::*;
+
+#[derive(Routable, Clone, PartialEq, Debug)]
+#[rustfmt::skip]
+pub enum Route {
+    #[route("/login?:redirect")]
+    LoginPage { redirect: String },
+
+    #[layout(Shell)]
+        #[route("/")]
+        Home {},
+        #[route("/apps/:app/:..segments")]
+        AppPage { app: String, segments: Vec<String> },
+    #[end_layout]
+
+    #[route("/:..segments")]
+    NotFound { segments: Vec<String> },
+}
+
+/// Replaces the current route once mounted.
+#[component]
+pub fn Redirect(to: String) -> Element {
+    use_effect(move || {
+        navigator().replace(to.clone());
+    });
+
+    rsx! {}
+}
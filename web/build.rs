@@ -0,0 +1,57 @@
+//! Generates the static app registration from the `[apps] enabled` core config.
+//!
+//! Every enabled app must have an entry in `[package.metadata.link.apps]` of this crate and a
+//! feature of the same name, apps whose feature is off are skipped with a warning.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let manifest_path = Path::new(&manifest_dir).join("Cargo.toml");
+    let core_config_path = Path::new(&manifest_dir).join("../core/config.toml");
+    println!("cargo:rerun-if-changed={}", manifest_path.display());
+    println!("cargo:rerun-if-changed={}", core_config_path.display());
+
+    let manifest: toml::Table = toml::from_str(&fs::read_to_string(&manifest_path).unwrap())
+        .expect("Failed to parse Cargo.toml");
+    let core_config: toml::Table = toml::from_str(&fs::read_to_string(&core_config_path).unwrap())
+        .expect("Failed to parse core config");
+
+    let app_paths = manifest
+        .get("package")
+        .and_then(|p| p.get("metadata"))
+        .and_then(|m| m.get("link"))
+        .and_then(|l| l.get("apps"))
+        .and_then(|a| a.as_table())
+        .cloned()
+        .unwrap_or_default();
+    let enabled = core_config
+        .get("apps")
+        .and_then(|a| a.get("enabled"))
+        .and_then(|e| e.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut entries = String::new();
+    for name in enabled.iter().filter_map(|n| n.as_str()) {
+        let Some(path) = app_paths.get(name).and_then(|p| p.as_str()) else {
+            println!("cargo:warning=App `{name}` is enabled, but has no plugin path");
+            continue;
+        };
+        let feature = format!("CARGO_FEATURE_{}", name.to_uppercase().replace('-', "_"));
+        if env::var_os(feature).is_none() {
+            println!("cargo:warning=App `{name}` is enabled, but its feature is off");
+            continue;
+        }
+        entries.push_str(&format!("    &{path},\n"));
+    }
+
+    let generated = format!(
+        "// generated by build.rs, do not edit\n\
+         pub static STATIC_APPS: &[&dcore::plugin::PluginDeclaration] = &[\n{entries}];\n"
+    );
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("apps.rs");
+    fs::write(out_path, generated).unwrap();
+}
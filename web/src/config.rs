@@ -11,6 +11,7 @@ pub struct WebConfig {}
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub core: CoreConfig,
+    #[allow(dead_code)]
     pub web: WebConfig,
 }
 
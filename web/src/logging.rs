@@ -1,7 +1,6 @@
 use fern::Dispatch;
 use log::LevelFilter;
 use std::io;
-use web_sys;
 
 pub fn init_logger() {
     Dispatch::new()
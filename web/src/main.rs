@@ -1,14 +1,19 @@
 use crate::storage::get_storage;
-use dcore::state::app::{load_active_app, register_app};
+use dcore::keys::{GLOBAL_SCOPE, configure_keymaps, register_keymap};
+use dcore::plugin::register_plugins;
+use dcore::state::app::load_active_app;
 use dcore::state::auth::SharedAuthState;
 use dioxus::prelude::*;
 use js_sys::eval;
 use lcore::prelude::*;
 use manganis::asset;
-use ui::messenger;
 
+mod apps {
+    include!(concat!(env!("OUT_DIR"), "/apps.rs"));
+}
 mod config;
 mod logging;
+mod socket;
 mod storage;
 
 const CSS: Asset = asset!("/assets/styling/web-app.css");
@@ -38,26 +43,37 @@ fn init() {
     use_context_provider(|| auth_state.clone());
 
     let storage = SharedStorage::new(get_storage());
-    use_context_provider(|| storage.clone());
+    ui::home::provide_shell_storage(storage.clone());
+
+    let shared_client =
+        use_context_provider(|| lcore::api::factory::get_shared_api_client(storage.clone()));
+    socket::use_message_socket(shared_client);
+
+    register_apps_from_config(storage.clone());
 
     let auth_manager = lcore::auth::factory::get_auth_manager(storage.clone());
     if auth_manager.is_authenticated() {
         auth_state.set_authenticated();
     }
-
-    let shared_client = lcore::api::factory::get_shared_api_client(storage.clone());
-    use_context_provider(|| shared_client);
-
-    register_apps_from_config(storage.clone());
 }
 
 pub fn register_apps_from_config(storage: SharedStorage) {
     let config = config::get_config();
 
-    // for now, they are hardcoded, I'll move to dlls later
-    if config.core.apps.is_app_enabled(messenger::NAME) {
-        register_app(messenger::NAME, messenger::Messenger);
+    for err in register_plugins(
+        apps::STATIC_APPS.iter().copied(),
+        &config.core.apps,
+        storage.clone(),
+    ) {
+        log::error!("{}", err);
     }
 
     load_active_app(storage);
+
+    for err in register_keymap(GLOBAL_SCOPE, ui::apps::SHELL_KEYMAP) {
+        log::error!("{}", err);
+    }
+    for err in configure_keymaps(&config.core.keybindings) {
+        log::error!("{}", err);
+    }
 }
@@ -0,0 +1,103 @@
+//! The message service socket, it publishes the events the service pushes and sends the
+//! commands the apps publish, while the user is logged in.
+
+use dcore::events::{publish, subscribe};
+use dcore::state::auth::IS_AUTHENTICATED;
+use dcore::utils::sleep;
+use dioxus::prelude::*;
+use futures::channel::mpsc;
+use futures::future::poll_fn;
+use futures::{SinkExt, StreamExt, select};
+use gloo_net::websocket::futures::WebSocket;
+use gloo_net::websocket::{Message, State, WebSocketError};
+use lcore::api::client::{ApiError, SharedApiClient};
+use lcore::api::schemas::WsCommand;
+use lcore::api::socket::{Backoff, UNAUTHORIZED, decode_event, encode_command};
+
+/// Connects on login and disconnects on logout.
+pub fn use_message_socket(client: SharedApiClient) {
+    // a restarted resource drops the previous future, and with it the connection
+    use_resource(move || {
+        let logged_in = IS_AUTHENTICATED();
+        let client = client.clone();
+        async move {
+            if logged_in {
+                run(client).await;
+            }
+        }
+    });
+}
+
+async fn run(client: SharedApiClient) {
+    let mut backoff = Backoff::default();
+    loop {
+        sleep(backoff.delay()).await;
+        let Some(auth) = client.message_socket_auth().await else {
+            return;
+        };
+        match open(&client.message_socket_url().await, auth).await {
+            Ok(socket) => match serve(socket).await {
+                // most likely the access token has expired
+                Err(WebSocketError::ConnectionClose(e)) if e.code == UNAUTHORIZED => {
+                    if let Err(ApiError::Unauthenticated) = client.refresh_auth().await {
+                        return;
+                    }
+                }
+                result => {
+                    backoff.on_connected();
+                    if let Err(e) = result {
+                        log::warn!("Message socket closed: {}", e);
+                    }
+                }
+            },
+            Err(e) => log::warn!("Failed to connect to the message socket: {}", e),
+        }
+        backoff.on_failure();
+    }
+}
+
+/// Connects and authenticates with the first frame.
+async fn open(url: &str, auth: String) -> Result<WebSocket, String> {
+    let mut socket = WebSocket::open(url).map_err(|e| e.to_string())?;
+    // ready once the handshake is over, whether it worked or not
+    poll_fn(|cx| socket.poll_ready_unpin(cx))
+        .await
+        .map_err(|e| e.to_string())?;
+    if !matches!(socket.state(), State::Open) {
+        return Err("the connection was refused".to_string());
+    }
+    socket
+        .send(Message::Text(auth))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+/// Runs until the connection is closed.
+async fn serve(socket: WebSocket) -> Result<(), WebSocketError> {
+    let (mut sink, frames) = socket.split();
+    let (sender, commands) = mpsc::unbounded();
+    let _subscription = subscribe(move |command: &WsCommand| {
+        let _ = sender.unbounded_send(command.clone());
+    });
+
+    let (mut frames, mut commands) = (frames.fuse(), commands.fuse());
+    loop {
+        select! {
+            frame = frames.next() => match frame {
+                Some(Ok(Message::Text(text))) => match decode_event(&text) {
+                    Ok(event) => publish(event),
+                    Err(e) => log::warn!("Invalid message socket event: {}", e),
+                },
+                Some(Ok(Message::Bytes(_))) => log::warn!("Unexpected binary message socket event"),
+                Some(Err(e)) => return Err(e),
+                None => return Ok(()),
+            },
+            command = commands.next() => {
+                if let Some(command) = command {
+                    sink.send(Message::Text(encode_command(&command))).await?;
+                }
+            }
+        }
+    }
+}
@@ -29,6 +29,19 @@ impl Storage for WebStorage {
             let _ = storage.remove_item(key);
         }
     }
+
+    fn keys(&self) -> Vec<String> {
+        let Some(storage) = web_sys::window()
+            .and_then(|w| w.local_storage().ok())
+            .flatten()
+        else {
+            return vec![];
+        };
+        let length = storage.length().unwrap_or(0);
+        (0..length)
+            .filter_map(|i| storage.key(i).ok().flatten())
+            .collect()
+    }
 }
 
 pub fn get_storage() -> WebStorage {